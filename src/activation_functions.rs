@@ -19,7 +19,7 @@ pub fn derivative(function: &ActivationFunction, x: f32) -> f32 {
         Sqrt => sqrt_derivative(x),
         Linear => linear_derivative(x),
         Relu => relu_derivative(x),
-        _ => x,
+        _ => 1.0,
     }
 }
 
@@ -55,7 +55,7 @@ fn linear(x: f32) -> f32 {
     x
 }
 
-fn linear_derivative(x: f32) -> f32 {
+fn linear_derivative(_x: f32) -> f32 {
     1.0
 }
 
@@ -0,0 +1,271 @@
+//! Forward and backward passes over a [`Network`].
+//!
+//! Sign conventions (L is the loss of one sample):
+//! * `Neuron::error` is `-dL/d(output)`, positive when the output has to grow.
+//!   For the output layer it is set by the caller from the loss, for hidden and
+//!   input layers it is the sum of `weight * delta` over the outgoing links.
+//! * `Neuron::delta` is `-dL/d(sum_input) = error * f'(sum_input)`.
+//! * Weights move along the delta: `weight += learning_rate * delta * source.output`,
+//!   i.e. `-dL/d(weight) = delta * source.output`.
+//!   The bias acts as a link from a constant 1.0 source: `bias += learning_rate * delta`.
+//!   How exactly the step is taken is up to the [`Optimizer`].
+//! * A neuron dropped by dropout has the output 0, kept ones are scaled by `1/(1-dropout)`;
+//!   the same multiplier applies to the delta.
+//!
+//! Link sources are taken from the [`crate::execution_plan::ExecutionPlan`] by index, ids are not looked up.
+use crate::activation_functions::{apply, derivative};
+use crate::nn_objects::Network;
+use crate::optimizers::Optimizer;
+use rand::Rng;
+
+pub fn forward(nn: &mut Network) -> Result<(), String> {
+    let plan = nn.compile_plan()?;
+    for layer_index in 1..nn.layers_count {
+        let (prev, current) = nn.layers.split_at_mut(layer_index);
+        let prev_layer = &prev[layer_index - 1];
+        let current_layer = &mut current[0];
+
+        for (neuron_index, neuron) in current_layer.neurons.iter_mut().enumerate() {
+            let sources = &plan.sources[layer_index][neuron_index];
+            let sum: f32 = neuron
+                .input_links
+                .iter()
+                .zip(sources)
+                .map(|(link, &source)| link.weight * prev_layer.neurons[source].output)
+                .fold(neuron.bias, |acc, e| acc + e);
+            neuron.sum_input = sum;
+            neuron.output = apply(&neuron.function_name, sum) * dropout_scale(&current_layer.dropout_mask, neuron_index);
+        }
+    }
+    Ok(())
+}
+
+/// Chooses the neurons dropped for the next train sample, the output layer is never dropped
+pub fn sample_dropout(nn: &mut Network, rng: &mut impl Rng) {
+    let output_index = nn.layers_count - 1;
+    for layer in nn.layers[1..output_index].iter_mut().filter(|l| l.dropout > 0.0) {
+        let keep_scale = 1.0 / (1.0 - layer.dropout);
+        layer.dropout_mask = (0..layer.neurons.len())
+            .map(|_| if rng.random::<f32>() < layer.dropout { 0.0 } else { keep_scale })
+            .collect();
+    }
+}
+
+/// Inference: every neuron is kept
+pub fn clear_dropout(nn: &mut Network) {
+    for layer in nn.layers.iter_mut() {
+        layer.dropout_mask.clear();
+    }
+}
+
+fn dropout_scale(mask: &[f32], neuron_index: usize) -> f32 {
+    mask.get(neuron_index).copied().unwrap_or(1.0)
+}
+
+/// Propagates the errors of the output layer down to the input layer.
+/// The output layer errors have to be set before the call.
+pub fn backward(nn: &mut Network) -> Result<(), String> {
+    let plan = nn.compile_plan()?;
+    for layer_index in (1..nn.layers_count).rev() {
+        let (prev, current) = nn.layers.split_at_mut(layer_index);
+        let prev_layer = &mut prev[layer_index - 1];
+        let current_layer = &mut current[0];
+
+        for (neuron_index, neuron) in current_layer.neurons.iter_mut().enumerate() {
+            neuron.delta = neuron.error * derivative(&neuron.function_name, neuron.sum_input)
+                * dropout_scale(&current_layer.dropout_mask, neuron_index);
+        }
+
+        //распространяем ошибку: каждый нейрон предыдущего слоя получает сумму ошибок по своим связям
+        for prev_neuron in prev_layer.neurons.iter_mut() {
+            prev_neuron.error = 0.0;
+        }
+        for (neuron, sources) in current_layer.neurons.iter().zip(&plan.sources[layer_index]) {
+            for (link, &source) in neuron.input_links.iter().zip(sources) {
+                prev_layer.neurons[source].error += link.weight * neuron.delta;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adds the gradients of the current sample (deltas computed by [`backward`]) to `gradients`.
+/// Parameters are numbered layer by layer, neuron by neuron: the input links first, then the bias.
+pub fn accumulate_gradients(nn: &mut Network, gradients: &mut Vec<f32>) -> Result<(), String> {
+    gradients.resize(nn.parameters_count(), 0.0);
+    let plan = nn.compile_plan()?;
+    let mut index = 0;
+    for layer_index in 1..nn.layers_count {
+        let prev_layer = &nn.layers[layer_index - 1];
+        for (neuron, sources) in nn.layers[layer_index].neurons.iter().zip(&plan.sources[layer_index]) {
+            for &source in sources {
+                gradients[index] += neuron.delta * prev_layer.neurons[source].output;
+                index += 1;
+            }
+            gradients[index] += neuron.delta;
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Applies the mean of the gradients accumulated over `samples` through the optimizer
+/// and resets them to zero.
+pub fn apply_gradients(nn: &mut Network, gradients: &mut [f32], samples: usize, optimizer: &mut dyn Optimizer, learning_rate: f32) {
+    optimizer.begin_step();
+    let scale = 1.0 / samples.max(1) as f32;
+    let mut index = 0;
+    for layer in nn.layers.iter_mut().skip(1) {
+        for neuron in layer.neurons.iter_mut() {
+            for link in neuron.input_links.iter_mut() {
+                link.weight += optimizer.delta(index, gradients[index] * scale, learning_rate);
+                index += 1;
+            }
+            neuron.bias += optimizer.delta(index, gradients[index] * scale, learning_rate);
+            index += 1;
+        }
+    }
+    gradients.fill(0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backpropagation::{accumulate_gradients, apply_gradients, backward, forward};
+    use crate::nn_objects::{ActivationFunction, Layer, Link, Network, Neuron};
+    use crate::optimizers::OptimizerState;
+
+    const EPSILON: f32 = 1e-3;
+    const TOLERANCE: f32 = 1e-2;
+
+    fn build(function: ActivationFunction) -> Network {
+        let a = Neuron::new_input("a".to_string());
+        let b = Neuron::new_input("b".to_string());
+        let h1 = Neuron::new_middle("h1".to_string(), 0.0, function.clone(),
+            vec![Link::new("a".to_string(), 0.4), Link::new("b".to_string(), 0.3)]);
+        let h2 = Neuron::new_middle("h2".to_string(), 0.0, function.clone(),
+            vec![Link::new("a".to_string(), 0.2), Link::new("b".to_string(), 0.7)]);
+        let y1 = Neuron::new_middle("y1".to_string(), 0.0, function.clone(),
+            vec![Link::new("h1".to_string(), 0.6), Link::new("h2".to_string(), 0.5)]);
+        let y2 = Neuron::new_middle("y2".to_string(), 0.0, function,
+            vec![Link::new("h1".to_string(), 0.3), Link::new("h2".to_string(), 0.8)]);
+        let mut nn = Network::new(vec![Layer::new(vec![a, b]), Layer::new(vec![h1, h2]), Layer::new(vec![y1, y2])]);
+        nn.layers[1].neurons[0].bias = 0.1;
+        nn.layers[2].neurons[1].bias = -0.2;
+        nn
+    }
+
+    const INPUTS: [f32; 2] = [0.9, 0.6];
+    const TARGETS: [f32; 2] = [0.1, 1.3];
+
+    /// L = 1/2 * sum((t - o)^2)
+    fn loss(nn: &mut Network) -> f32 {
+        for (neuron, value) in nn.layers[0].neurons.iter_mut().zip(INPUTS) {
+            neuron.output = value;
+        }
+        forward(nn).unwrap();
+        nn.last().neurons.iter().zip(TARGETS)
+            .map(|(n, t)| 0.5 * (t - n.output).powi(2))
+            .sum()
+    }
+
+    fn check_gradients(function: ActivationFunction, dropout_mask: &[f32]) {
+        let mut nn = build(function.clone());
+        nn.layers[1].dropout_mask = dropout_mask.to_vec();
+        loss(&mut nn);
+        let last = nn.layers_count - 1;
+        for (neuron, target) in nn.layers[last].neurons.iter_mut().zip(TARGETS) {
+            neuron.error = target - neuron.output;
+        }
+        backward(&mut nn).unwrap();
+
+        for layer_index in 1..nn.layers_count {
+            for neuron_index in 0..nn.layers[layer_index].neurons.len() {
+                for link_index in 0..nn.layers[layer_index].neurons[neuron_index].input_links.len() {
+                    let neuron = &nn.layers[layer_index].neurons[neuron_index];
+                    let source = nn.layers[layer_index - 1].get_value(&neuron.input_links[link_index].source_id);
+                    let analytic = -neuron.delta * source;
+
+                    let mut plus = nn.clone();
+                    plus.layers[layer_index].neurons[neuron_index].input_links[link_index].weight += EPSILON;
+                    let mut minus = nn.clone();
+                    minus.layers[layer_index].neurons[neuron_index].input_links[link_index].weight -= EPSILON;
+                    let numeric = (loss(&mut plus) - loss(&mut minus)) / (2.0 * EPSILON);
+
+                    assert!(
+                        (analytic - numeric).abs() < TOLERANCE,
+                        "{:?} {}->{}: analytic {}, numeric {}",
+                        function, neuron.input_links[link_index].source_id, neuron.id, analytic, numeric
+                    );
+                }
+
+                let neuron = &nn.layers[layer_index].neurons[neuron_index];
+                let analytic = -neuron.delta;
+                let mut plus = nn.clone();
+                plus.layers[layer_index].neurons[neuron_index].bias += EPSILON;
+                let mut minus = nn.clone();
+                minus.layers[layer_index].neurons[neuron_index].bias -= EPSILON;
+                let numeric = (loss(&mut plus) - loss(&mut minus)) / (2.0 * EPSILON);
+                assert!(
+                    (analytic - numeric).abs() < TOLERANCE,
+                    "{:?} bias of {}: analytic {}, numeric {}",
+                    function, neuron.id, analytic, numeric
+                );
+            }
+        }
+    }
+
+    fn sample_gradients(nn: &mut Network, inputs: [f32; 2]) -> Vec<f32> {
+        for (neuron, value) in nn.layers[0].neurons.iter_mut().zip(inputs) {
+            neuron.output = value;
+        }
+        forward(nn).unwrap();
+        let last = nn.layers_count - 1;
+        for (neuron, target) in nn.layers[last].neurons.iter_mut().zip(TARGETS) {
+            neuron.error = target - neuron.output;
+        }
+        backward(nn).unwrap();
+        let mut gradients = vec![];
+        accumulate_gradients(nn, &mut gradients).unwrap();
+        gradients
+    }
+
+    #[test]
+    fn batch_applies_mean_gradient() {
+        let mut nn = build(ActivationFunction::Sigmoid);
+        let first = sample_gradients(&mut nn.clone(), [0.9, 0.6]);
+        let second = sample_gradients(&mut nn.clone(), [-0.4, 0.2]);
+
+        let mut gradients = first.clone();
+        for (sum, g) in gradients.iter_mut().zip(second.iter()) {
+            *sum += g;
+        }
+        let before = nn.clone();
+        let mut optimizer = crate::optimizers::build(OptimizerState::default());
+        apply_gradients(&mut nn, &mut gradients, 2, optimizer.as_mut(), 1.0);
+
+        let expected = (first[0] + second[0]) / 2.0;
+        let actual = nn.layers[1].neurons[0].input_links[0].weight - before.layers[1].neurons[0].input_links[0].weight;
+        assert!((expected - actual).abs() < 1e-6, "expected {}, got {}", expected, actual);
+        assert!(gradients.iter().all(|g| *g == 0.0));
+    }
+
+    #[test]
+    fn gradient_check() {
+        for function in [
+            ActivationFunction::None,
+            ActivationFunction::Sigmoid,
+            ActivationFunction::Square,
+            ActivationFunction::Sqrt,
+            ActivationFunction::Linear,
+            ActivationFunction::Relu,
+        ] {
+            check_gradients(function, &[]);
+        }
+    }
+
+    #[test]
+    fn gradient_check_with_dropout() {
+        check_gradients(ActivationFunction::Sigmoid, &[0.0, 2.0]);
+        check_gradients(ActivationFunction::Relu, &[2.0, 0.0]);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::execution_objects::TrainProgress;
+use crate::lr_schedules::LrScheduleState;
+use crate::nn_objects::Network;
+use crate::optimizers::OptimizerState;
+use crate::validation::describe;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Everything needed to continue a training run exactly where it stopped.
+/// The data set is not stored: it is recreated from `network.metadata.seed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub network: Network,
+    pub iteration: usize,
+    pub progress: TrainProgress,
+    pub schedule: LrScheduleState,
+    pub optimizer: OptimizerState,
+    pub rng: ChaCha8Rng,
+    /// Unfinished mini-batch
+    pub batch_samples: usize,
+    pub gradients: Vec<f32>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let checkpoint: Checkpoint = serde_json::from_str(&fs::read_to_string(path)?)?;
+        checkpoint.network.validate().map_err(|errors| format!("{}: {}", path.display(), describe(&errors)))?;
+        Ok(checkpoint)
+    }
+
+    /// Writes next to `path` first, so a killed process never leaves a broken checkpoint
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use crate::dataset::ColumnMapping;
+use crate::generators::GeneratorFunction;
+use crate::lr_schedules::LrScheduleFunction;
+use crate::nn_objects::LossFunction;
+use crate::normalization::Scaling;
+use crate::optimizers::OptimizerFunction;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Parser)]
+#[command(about = "Neural network playground: y = kx + b, ax^2 + bx + c = 0")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Train a network, with the UI window unless --headless
+    Train(Box<TrainArgs>),
+    /// Loss and errors of a network on a dataset
+    Eval(EvalArgs),
+    /// Outputs of a network for the given inputs
+    Predict(PredictArgs),
+    /// Layers, neurons and links of a network
+    Inspect(InspectArgs),
+    /// Save a network in another format: binary for .bin, json otherwise
+    Convert(ConvertArgs),
+    /// Write a synthetic dataset: CSV for .csv, json otherwise
+    Generate(GenerateArgs),
+}
+
+/// Networks from `nn_build`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Builtin {
+    /// y = kx + b, inputs k, x, b
+    KxB,
+    /// roots of ax^2 + bx + c = 0, inputs a, b, c
+    Ax2BxC,
+}
+
+/// How training runs the network
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Backend {
+    /// Neuron by neuron, link by link, the UI shows every step
+    Graph,
+    /// Weight matrix per layer, a mini-batch at once
+    Dense,
+}
+
+#[derive(Args)]
+pub struct NetworkArgs {
+    /// Network file, the built-in network is used when omitted
+    #[arg(long)]
+    pub network: Option<PathBuf>,
+    /// Topology description (json) to build a new network from
+    #[arg(long, conflicts_with = "network")]
+    pub topology: Option<PathBuf>,
+    #[arg(long, value_enum, default_value = "kx-b")]
+    pub builtin: Builtin,
+}
+
+#[derive(Args)]
+pub struct DatasetArgs {
+    /// Dataset file: CSV with a header row (.csv) or a json array of objects.
+    /// Samples are generated when omitted, y = kx + b by default
+    #[arg(long, conflicts_with = "GeneratorArgs")]
+    pub dataset: Option<PathBuf>,
+    /// Columns of the neurons named differently, "k=slope,b=offset".
+    /// By default the column of a neuron is its id
+    #[arg(long, requires = "dataset")]
+    pub columns: Option<ColumnMapping>,
+    #[command(flatten)]
+    pub generator: GeneratorArgs,
+}
+
+/// Synthetic samples, values not given are taken from --generator-file or the defaults:
+/// 100 integer samples of y = kx + b in -10..10
+#[derive(Args)]
+pub struct GeneratorArgs {
+    /// linear, quadratic, quadratic-complex, quadratic-class, polynomial, sin, cos, xor, spirals or product
+    #[arg(long)]
+    pub generator: Option<GeneratorFunction>,
+    /// Generator configuration as json
+    #[arg(long, conflicts_with = "generator")]
+    pub generator_file: Option<PathBuf>,
+    /// Number of generated samples
+    #[arg(long)]
+    pub samples: Option<usize>,
+    /// Smallest drawn input value
+    #[arg(long, allow_negative_numbers = true)]
+    pub min: Option<f32>,
+    #[arg(long, allow_negative_numbers = true)]
+    pub max: Option<f32>,
+    /// Whole input values only: true or false
+    #[arg(long)]
+    pub integer: Option<bool>,
+    /// Standard deviation of the gaussian noise
+    #[arg(long)]
+    pub noise: Option<f32>,
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    #[command(flatten)]
+    pub generator: GeneratorArgs,
+    #[arg(long)]
+    pub output: PathBuf,
+    /// Random when omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct TrainArgs {
+    #[command(flatten)]
+    pub network: NetworkArgs,
+    #[command(flatten)]
+    pub dataset: DatasetArgs,
+    /// Where the trained network is written (binary for .bin), optimizer state goes next to it
+    #[arg(long, default_value = "nn.json")]
+    pub output: PathBuf,
+    /// Stop after this many epochs even if the target error is not reached
+    #[arg(long)]
+    pub epochs: Option<usize>,
+    /// Stop when the validation error is below
+    #[arg(long, default_value_t = 0.001)]
+    pub target_error: f32,
+    #[arg(long, default_value_t = 0.01, conflicts_with = "resume")]
+    pub learning_rate: f32,
+    /// Seed for initial weights, dataset generation and splitting, random when omitted
+    #[arg(long, conflicts_with = "resume")]
+    pub seed: Option<u64>,
+    /// mse, mae, huber, log-cosh or relative, taken from the network file when omitted
+    #[arg(long)]
+    pub loss: Option<LossFunction>,
+    /// sgd, momentum, nesterov, rmsprop, adagrad or adam, the saved state is continued when omitted
+    #[arg(long, conflicts_with = "resume")]
+    pub optimizer: Option<OptimizerFunction>,
+    /// constant, step, exponential, cosine, warmup or plateau
+    #[arg(long, conflicts_with = "resume")]
+    pub lr_schedule: Option<LrScheduleFunction>,
+    /// Learning rate schedule as json
+    #[arg(long, conflicts_with_all = ["lr_schedule", "resume"])]
+    pub lr_schedule_file: Option<PathBuf>,
+    /// Samples per weight update: a number or "full"
+    #[arg(long, default_value = "1")]
+    pub batch_size: BatchSize,
+    #[arg(long, default_value_t = 0.15)]
+    pub validation_split: f32,
+    #[arg(long, default_value_t = 0.15)]
+    pub test_split: f32,
+    /// Scaling of the inputs fitted on the train data: none, min-max, z-score or log.
+    /// Saved with the network and applied at inference
+    #[arg(long)]
+    pub normalize_inputs: Option<Scaling>,
+    /// Scaling of the outputs, predictions are converted back to the original units
+    #[arg(long)]
+    pub normalize_outputs: Option<Scaling>,
+    /// No UI window, run at full speed
+    #[arg(long)]
+    pub headless: bool,
+    /// Show the values of the input and output neurons in the original units as well
+    #[arg(long, conflicts_with = "headless")]
+    pub show_raw: bool,
+    /// dense: weight matrices, batched; only without the UI
+    #[arg(long, value_enum, default_value = "graph")]
+    pub backend: Backend,
+    /// Append progress to this file as well
+    #[arg(long)]
+    pub log: Option<PathBuf>,
+    /// Report progress every N epochs
+    #[arg(long, default_value_t = 100)]
+    pub log_every: usize,
+    /// Checkpoint file, next to --output when omitted (nn.checkpoint.json)
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+    /// Write a checkpoint every N epochs, 0 - only on pause
+    #[arg(long, default_value_t = 100)]
+    pub checkpoint_every: usize,
+    /// Continue from a checkpoint, with the same network and dataset options as the interrupted run.
+    /// The optimizer and learning rate schedule are taken from the checkpoint
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
+    /// When a value becomes NaN or infinite, go back to the last checkpoint with the learning rate
+    /// multiplied by this factor (0..1) instead of stopping the run
+    #[arg(long, value_name = "LR_FACTOR")]
+    pub rollback: Option<f32>,
+}
+
+#[derive(Args)]
+pub struct EvalArgs {
+    #[command(flatten)]
+    pub network: NetworkArgs,
+    #[command(flatten)]
+    pub dataset: DatasetArgs,
+    /// Taken from the network file when omitted
+    #[arg(long)]
+    pub loss: Option<LossFunction>,
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct PredictArgs {
+    #[command(flatten)]
+    pub network: NetworkArgs,
+    /// Values of the input neurons, comma separated
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true, required = true)]
+    pub inputs: Vec<f32>,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    #[command(flatten)]
+    pub network: NetworkArgs,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    #[command(flatten)]
+    pub network: NetworkArgs,
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+#[derive(Clone, Copy)]
+pub enum BatchSize {
+    Samples(usize),
+    Full,
+}
+
+impl FromStr for BatchSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(BatchSize::Full),
+            _ => match s.parse() {
+                Ok(size) if size > 0 => Ok(BatchSize::Samples(size)),
+                _ => Err(format!("batch size must be a positive number or \"full\": {s}")),
+            },
+        }
+    }
+}
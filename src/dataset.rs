@@ -0,0 +1,273 @@
+//! Datasets of any width: a table (CSV with a header row or a json array of objects)
+//! whose columns are bound to input and output neurons by id.
+use crate::nn_objects::Network;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Values of the input neurons and expected values of the output neurons, in the order of the layers.
+/// An expected value can be NaN (null in json): no target, the output is not trained or scored on this sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub inputs: Vec<f32>,
+    pub outputs: Vec<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    /// Ids of the input neurons, one per value of `Sample::inputs`
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub samples: Vec<Sample>,
+}
+
+/// Neuron id -> column name, for the neurons whose column is named differently
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping(pub HashMap<String, String>);
+
+impl ColumnMapping {
+    fn column<'a>(&'a self, neuron_id: &'a str) -> &'a str {
+        self.0.get(neuron_id).map_or(neuron_id, |c| c.as_str())
+    }
+}
+
+/// "k=slope,b=offset"
+impl FromStr for ColumnMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mapping = HashMap::new();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (neuron_id, column) = pair.split_once('=')
+                .ok_or(format!("expected neuron=column, got \"{pair}\""))?;
+            mapping.insert(neuron_id.trim().to_string(), column.trim().to_string());
+        }
+        Ok(ColumnMapping(mapping))
+    }
+}
+
+/// Raw cells, `None` for a field missing in a json object
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+impl Table {
+    pub fn from_csv(content: &str) -> Result<Self, String> {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(content.as_bytes());
+        let columns = reader.headers().map_err(|e| e.to_string())?.iter().map(String::from).collect();
+        let mut rows = vec![];
+        for record in reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            rows.push(record.iter().map(|cell| Some(cell.to_string())).collect());
+        }
+        Ok(Table { columns, rows })
+    }
+
+    /// Numbers and numeric strings are accepted as values, null is NaN (no target)
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let objects: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(content)
+            .map_err(|e| format!("expected an array of objects: {e}"))?;
+        let mut columns: Vec<String> = vec![];
+        for key in objects.iter().flat_map(|o| o.keys()) {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+        let rows = objects.iter()
+            .map(|object| columns.iter()
+                .map(|column| match object.get(column) {
+                    None => None,
+                    Some(serde_json::Value::Null) => Some("NaN".to_string()),
+                    Some(serde_json::Value::String(s)) => Some(s.clone()),
+                    Some(value) => Some(value.to_string()),
+                })
+                .collect())
+            .collect();
+        Ok(Table { columns, rows })
+    }
+
+    fn column_index(&self, neuron_id: &str, mapping: &ColumnMapping) -> Result<usize, String> {
+        let column = mapping.column(neuron_id);
+        self.columns.iter().position(|c| c == column)
+            .ok_or(format!("no column \"{column}\" for neuron {neuron_id}, columns: {}", self.columns.join(", ")))
+    }
+
+    /// Old positional files: input_1.., output_1..
+    fn positional_mapping(&self, inputs: &[String], outputs: &[String]) -> Option<ColumnMapping> {
+        if !self.columns.iter().any(|c| c == "input_1") {
+            return None;
+        }
+        let inputs = inputs.iter().enumerate().map(|(i, id)| (id.clone(), format!("input_{}", i + 1)));
+        let outputs = outputs.iter().enumerate().map(|(i, id)| (id.clone(), format!("output_{}", i + 1)));
+        Some(ColumnMapping(inputs.chain(outputs).collect()))
+    }
+}
+
+impl Dataset {
+    pub fn from_table(table: &Table, inputs: &[String], outputs: &[String], mapping: &ColumnMapping) -> Result<Self, String> {
+        let indices = |ids: &[String]| -> Result<Vec<usize>, String> {
+            ids.iter().map(|id| table.column_index(id, mapping)).collect()
+        };
+        let (input_columns, output_columns) = (indices(inputs)?, indices(outputs)?);
+        let values = |row_index: usize, row: &[Option<String>], columns: &[usize], targets: bool| -> Result<Vec<f32>, String> {
+            columns.iter()
+                .map(|&column| {
+                    let name = &table.columns[column];
+                    let cell = row.get(column).cloned().flatten()
+                        .ok_or(format!("row {}: {name} is missing", row_index + 1))?;
+                    match cell.parse::<f32>() {
+                        Ok(value) if value.is_nan() && !targets =>
+                            Err(format!("row {}, column {name}: an input has to have a value", row_index + 1)),
+                        Ok(value) => Ok(value),
+                        Err(_) => Err(format!("row {}, column {name}: \"{cell}\" is not a number", row_index + 1)),
+                    }
+                })
+                .collect()
+        };
+        let samples = table.rows.iter().enumerate()
+            .map(|(index, row)| Ok(Sample {
+                inputs: values(index, row, &input_columns, false)?,
+                outputs: values(index, row, &output_columns, true)?,
+            }))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Dataset { inputs: inputs.to_vec(), outputs: outputs.to_vec(), samples })
+    }
+
+    /// Binds the file to the input and output neurons of `nn`: CSV for `.csv`, json otherwise
+    pub fn load(path: &Path, nn: &Network, mapping: &ColumnMapping) -> Result<Self, String> {
+        let with_path = |e: String| format!("{}: {e}", path.display());
+        let content = fs::read_to_string(path).map_err(|e| with_path(e.to_string()))?;
+        let table = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+            Table::from_csv(&content)
+        } else {
+            Table::from_json(&content)
+        }.map_err(with_path)?;
+
+        let ids = |index: usize| -> Vec<String> { nn.layers[index].neurons.iter().map(|n| n.id.clone()).collect() };
+        let (inputs, outputs) = (ids(0), ids(nn.layers_count - 1));
+        let positional = match mapping.0.is_empty() {
+            true => table.positional_mapping(&inputs, &outputs),
+            false => None,
+        };
+        Dataset::from_table(&table, &inputs, &outputs, positional.as_ref().unwrap_or(mapping)).map_err(with_path)
+    }
+
+    fn columns(&self) -> Vec<&String> {
+        self.inputs.iter().chain(self.outputs.iter()).collect()
+    }
+
+    /// Array of objects, one per sample, fields in the order of the columns
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        let rows: Vec<Row> = self.samples.iter()
+            .map(|sample| Row(self.columns().into_iter().zip(sample.inputs.iter().chain(sample.outputs.iter())).collect()))
+            .collect();
+        serde_json::to_string_pretty(&rows)
+    }
+
+    pub fn to_csv(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record(self.columns())?;
+        for sample in self.samples.iter() {
+            writer.write_record(sample.inputs.iter().chain(sample.outputs.iter()).map(|v| v.to_string()))?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    /// CSV for `.csv`, json otherwise, readable by [`Dataset::load`]
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let content = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+            self.to_csv()?
+        } else {
+            self.to_json()?
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+struct Row<'a>(Vec<(&'a String, &'a f32)>);
+
+impl Serialize for Row<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::{ColumnMapping, Dataset, Sample, Table};
+    use crate::nn_build::build_nn;
+    use std::path::Path;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn csv_with_mapping() {
+        let table = Table::from_csv("slope, x, b, y\n2, 3, 1, 7\n-1, 0.5, 0, -0.5\n").unwrap();
+        let mapping: ColumnMapping = "k=slope".parse().unwrap();
+        let dataset = Dataset::from_table(&table, &ids(&["k", "x", "b"]), &ids(&["y"]), &mapping).unwrap();
+        assert_eq!(dataset.samples, vec![
+            Sample { inputs: vec![2.0, 3.0, 1.0], outputs: vec![7.0] },
+            Sample { inputs: vec![-1.0, 0.5, 0.0], outputs: vec![-0.5] },
+        ]);
+    }
+
+    #[test]
+    fn json_objects() {
+        let table = Table::from_json(r#"[{"y": 7, "k": 2, "x": "3", "b": 1, "comment": "ok"}]"#).unwrap();
+        let dataset = Dataset::from_table(&table, &ids(&["k", "x", "b"]), &ids(&["y"]), &ColumnMapping::default()).unwrap();
+        assert_eq!(dataset.samples[0], Sample { inputs: vec![2.0, 3.0, 1.0], outputs: vec![7.0] });
+    }
+
+    #[test]
+    fn readable_errors() {
+        let inputs = ids(&["k", "x"]);
+        let outputs = ids(&["y"]);
+        let none = ColumnMapping::default();
+
+        let table = Table::from_csv("k,x\n1,2\n").unwrap();
+        assert_eq!(Dataset::from_table(&table, &inputs, &outputs, &none).unwrap_err(),
+                   "no column \"y\" for neuron y, columns: k, x");
+
+        let table = Table::from_csv("k,x,y\n1,2,3\n1,two,3\n").unwrap();
+        assert_eq!(Dataset::from_table(&table, &inputs, &outputs, &none).unwrap_err(),
+                   "row 2, column x: \"two\" is not a number");
+
+        let table = Table::from_json(r#"[{"k": 1, "x": 2, "y": 3}, {"k": 1, "y": 3}]"#).unwrap();
+        assert_eq!(Dataset::from_table(&table, &inputs, &outputs, &none).unwrap_err(), "row 2: x is missing");
+
+        let table = Table::from_json(r#"[{"k": 1, "x": 2, "y": null}, {"k": 1, "x": null, "y": 3}]"#).unwrap();
+        assert_eq!(Dataset::from_table(&table, &inputs, &outputs, &none).unwrap_err(),
+                   "row 2, column x: an input has to have a value");
+
+        assert!("k".parse::<ColumnMapping>().is_err());
+    }
+
+    #[test]
+    fn quadratic_train_file() {
+        let nn = build_nn(&mut rand::rng());
+        let dataset = Dataset::load(Path::new("neural-networks/ax2_bx_c/train.json"), &nn, &ColumnMapping::default()).unwrap();
+        assert_eq!(dataset.inputs, ids(&["a", "b", "c"]));
+        assert_eq!(dataset.outputs, ids(&["x1", "x2"]));
+        assert!(!dataset.samples.is_empty());
+        assert!(dataset.samples.iter().all(|s| s.inputs.len() == 3 && s.outputs.len() == 2));
+    }
+
+    #[test]
+    fn saved_files_load_back() {
+        let dataset = Dataset {
+            inputs: ids(&["k", "x", "b"]),
+            outputs: ids(&["y"]),
+            samples: vec![Sample { inputs: vec![2.0, 3.5, -1.0], outputs: vec![6.0] }],
+        };
+        for table in [Table::from_csv(&dataset.to_csv().unwrap()), Table::from_json(&dataset.to_json().unwrap())] {
+            let loaded = Dataset::from_table(&table.unwrap(), &dataset.inputs, &dataset.outputs, &ColumnMapping::default()).unwrap();
+            assert_eq!(loaded.samples, dataset.samples);
+        }
+    }
+}
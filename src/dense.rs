@@ -0,0 +1,300 @@
+//! Dense backend: every layer is a weight matrix and a bias vector, a batch of samples
+//! goes through the network as a matrix (one row per sample).
+//! Missing links (one-to-one layers) stay zero. Dropout is not supported.
+//! Signs and parameter order are the same as in [`crate::backpropagation`].
+use crate::activation_functions::{apply, derivative};
+use crate::execution_plan::ExecutionPlan;
+use crate::nn_objects::{ActivationFunction, Network};
+use crate::optimizers::Optimizer;
+
+/// Row-major matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f32>,
+}
+
+impl Matrix {
+    pub fn is_finite(&self) -> bool {
+        self.data.iter().all(|v| v.is_finite())
+    }
+
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix { rows, cols, data: vec![0.0; rows * cols] }
+    }
+
+    pub fn from_rows<'a>(cols: usize, rows: impl Iterator<Item = &'a [f32]>) -> Self {
+        let mut data = vec![];
+        for row in rows {
+            data.extend_from_slice(&row[..cols]);
+        }
+        Matrix { rows: data.len() / cols.max(1), cols, data }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f32 {
+        self.data[row * self.cols + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: f32) {
+        self.data[row * self.cols + col] = value;
+    }
+
+    pub fn row(&self, row: usize) -> &[f32] {
+        &self.data[row * self.cols..(row + 1) * self.cols]
+    }
+
+    /// self * other^T
+    pub fn mul_transposed(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.cols);
+        let mut result = Matrix::zeros(self.rows, other.rows);
+        for i in 0..self.rows {
+            for j in 0..other.rows {
+                let value = self.row(i).iter().zip(other.row(j)).map(|(a, b)| a * b).sum();
+                result.set(i, j, value);
+            }
+        }
+        result
+    }
+
+    /// self * other
+    pub fn mul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows);
+        let mut result = Matrix::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self.get(i, k);
+                for j in 0..other.cols {
+                    result.data[i * other.cols + j] += a * other.get(k, j);
+                }
+            }
+        }
+        result
+    }
+
+    /// self^T * other
+    pub fn transposed_mul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.rows, other.rows);
+        let mut result = Matrix::zeros(self.cols, other.cols);
+        for k in 0..self.rows {
+            for i in 0..self.cols {
+                let a = self.get(k, i);
+                for j in 0..other.cols {
+                    result.data[i * other.cols + j] += a * other.get(k, j);
+                }
+            }
+        }
+        result
+    }
+}
+
+pub struct DenseLayer {
+    /// Neurons of the layer x neurons of the previous layer
+    pub weights: Matrix,
+    pub bias: Vec<f32>,
+    pub activations: Vec<ActivationFunction>,
+    /// Columns of the links of each neuron, in the order of `Neuron::input_links`
+    pub links: Vec<Vec<usize>>,
+}
+
+/// Layers after the input layer
+pub struct DenseNetwork {
+    pub inputs: usize,
+    pub layers: Vec<DenseLayer>,
+}
+
+/// Results of a forward pass, `outputs[0]` are the inputs
+pub struct Activations {
+    pub sums: Vec<Matrix>,
+    pub outputs: Vec<Matrix>,
+}
+
+impl DenseNetwork {
+    pub fn from_network(nn: &Network) -> Result<Self, String> {
+        if nn.layers.iter().any(|l| l.dropout > 0.0) {
+            return Err("dense backend does not support dropout".to_string());
+        }
+        let plan = ExecutionPlan::compile(nn)?;
+        let mut layers = vec![];
+        for (layer_index, pair) in nn.layers.windows(2).enumerate() {
+            let (prev, layer) = (&pair[0], &pair[1]);
+            let sources = &plan.sources[layer_index + 1];
+            let mut weights = Matrix::zeros(layer.neurons.len(), prev.neurons.len());
+            for (row, (neuron, neuron_sources)) in layer.neurons.iter().zip(sources).enumerate() {
+                for (link, &col) in neuron.input_links.iter().zip(neuron_sources) {
+                    if neuron_sources.iter().filter(|&&c| c == col).count() > 1 {
+                        return Err(format!("neuron {} has several links from {}", neuron.id, link.source_id));
+                    }
+                    weights.set(row, col, link.weight);
+                }
+            }
+            layers.push(DenseLayer {
+                weights,
+                bias: layer.neurons.iter().map(|n| n.bias).collect(),
+                activations: layer.neurons.iter().map(|n| n.function_name.clone()).collect(),
+                links: sources.clone(),
+            });
+        }
+        Ok(DenseNetwork { inputs: nn.layers.first().map_or(0, |l| l.neurons.len()), layers })
+    }
+
+    /// Copies weights and biases back, `nn` has to be the network this one was made from
+    pub fn write_to(&self, nn: &mut Network) {
+        for (layer, dense) in nn.layers.iter_mut().skip(1).zip(self.layers.iter()) {
+            for (row, neuron) in layer.neurons.iter_mut().enumerate() {
+                for (link, &col) in neuron.input_links.iter_mut().zip(&dense.links[row]) {
+                    link.weight = dense.weights.get(row, col);
+                }
+                neuron.bias = dense.bias[row];
+            }
+        }
+    }
+
+    /// No NaN or infinite weights and biases
+    pub fn is_finite(&self) -> bool {
+        self.layers.iter().all(|l| l.weights.is_finite() && l.bias.iter().all(|v| v.is_finite()))
+    }
+
+    /// `inputs`: one row per sample
+    pub fn forward(&self, inputs: Matrix) -> Activations {
+        let mut sums = vec![];
+        let mut outputs = vec![inputs];
+        for layer in self.layers.iter() {
+            let mut sum = outputs.last().unwrap().mul_transposed(&layer.weights);
+            let mut output = Matrix::zeros(sum.rows, sum.cols);
+            for row in 0..sum.rows {
+                for col in 0..sum.cols {
+                    let value = layer.bias[col] + sum.get(row, col);
+                    sum.set(row, col, value);
+                    output.set(row, col, apply(&layer.activations[col], value));
+                }
+            }
+            sums.push(sum);
+            outputs.push(output);
+        }
+        Activations { sums, outputs }
+    }
+
+    /// `errors`: -dL/d(output) of the output layer, one row per sample.
+    /// Returns -dL/d(parameter) summed over the batch, in the order of [`crate::backpropagation::accumulate_gradients`]
+    pub fn backward(&self, activations: &Activations, errors: Matrix) -> Vec<f32> {
+        let mut layer_gradients = vec![];
+        let mut errors = errors;
+        for (index, layer) in self.layers.iter().enumerate().rev() {
+            let sum = &activations.sums[index];
+            let mut delta = errors;
+            for row in 0..delta.rows {
+                for col in 0..delta.cols {
+                    let value = delta.get(row, col) * derivative(&layer.activations[col], sum.get(row, col));
+                    delta.set(row, col, value);
+                }
+            }
+            let weight_gradients = delta.transposed_mul(&activations.outputs[index]);
+            let bias_gradients: Vec<f32> = (0..delta.cols)
+                .map(|col| (0..delta.rows).map(|row| delta.get(row, col)).sum())
+                .collect();
+            errors = delta.mul(&layer.weights);
+
+            let mut gradients = vec![];
+            for (row, links) in layer.links.iter().enumerate() {
+                gradients.extend(links.iter().map(|&col| weight_gradients.get(row, col)));
+                gradients.push(bias_gradients[row]);
+            }
+            layer_gradients.push(gradients);
+        }
+        layer_gradients.into_iter().rev().flatten().collect()
+    }
+
+    /// Same as [`crate::backpropagation::apply_gradients`]
+    pub fn apply_gradients(&mut self, gradients: &[f32], samples: usize, optimizer: &mut dyn Optimizer, learning_rate: f32) {
+        optimizer.begin_step();
+        let scale = 1.0 / samples.max(1) as f32;
+        let mut index = 0;
+        for layer in self.layers.iter_mut() {
+            for (row, links) in layer.links.iter().enumerate() {
+                for &col in links {
+                    let weight = layer.weights.get(row, col) + optimizer.delta(index, gradients[index] * scale, learning_rate);
+                    layer.weights.set(row, col, weight);
+                    index += 1;
+                }
+                layer.bias[row] += optimizer.delta(index, gradients[index] * scale, learning_rate);
+                index += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backpropagation::{accumulate_gradients, backward, forward};
+    use crate::dense::{DenseNetwork, Matrix};
+    use crate::execution_context::set_inputs;
+    use crate::topology::TopologySpec;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const TOLERANCE: f32 = 1e-5;
+    const SAMPLES: [[f32; 3]; 3] = [[0.5, -1.0, 2.0], [1.5, 0.25, -0.75], [-2.0, 0.0, 1.0]];
+    const TARGETS: [[f32; 2]; 3] = [[1.0, 0.0], [-0.5, 2.0], [0.25, 0.75]];
+
+    fn network() -> crate::nn_objects::Network {
+        TopologySpec::from_json(r#"{
+            "inputs": ["a", "b", "c"],
+            "layers": [
+                {"neurons": {"size": 5, "prefix": "h"}, "activation": "Sigmoid", "initializer": "XavierUniform"},
+                {"neurons": {"size": 5, "prefix": "g"}, "activation": "Relu", "connectivity": "OneToOne", "initializer": "HeNormal"},
+                {"neurons": ["y1", "y2"], "activation": "Linear", "initializer": "XavierNormal"}
+            ]
+        }"#).unwrap().build(&mut StdRng::seed_from_u64(3)).unwrap()
+    }
+
+    fn assert_close(expected: &[f32], actual: &[f32]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual) {
+            assert!((e - a).abs() < TOLERANCE, "expected {expected:?}, got {actual:?}");
+        }
+    }
+
+    #[test]
+    fn forward_and_gradients_match_graph() {
+        let mut nn = network();
+        let dense = DenseNetwork::from_network(&nn).unwrap();
+        let activations = dense.forward(Matrix::from_rows(3, SAMPLES.iter().map(|s| s.as_slice())));
+        let mut errors = Matrix::zeros(SAMPLES.len(), 2);
+
+        let mut expected_gradients = vec![];
+        for (row, (sample, targets)) in SAMPLES.iter().zip(TARGETS).enumerate() {
+            set_inputs(&mut nn, sample).unwrap();
+            forward(&mut nn).unwrap();
+            let outputs: Vec<f32> = nn.last().neurons.iter().map(|n| n.output).collect();
+            assert_close(&outputs, activations.outputs[3].row(row));
+
+            let last = nn.layers_count - 1;
+            for (col, (neuron, target)) in nn.layers[last].neurons.iter_mut().zip(targets).enumerate() {
+                neuron.error = target - neuron.output;
+                errors.set(row, col, neuron.error);
+            }
+            backward(&mut nn).unwrap();
+            accumulate_gradients(&mut nn, &mut expected_gradients).unwrap();
+        }
+
+        let gradients = dense.backward(&activations, errors);
+        assert_eq!(gradients.len(), nn.parameters_count());
+        assert_close(&expected_gradients, &gradients);
+    }
+
+    #[test]
+    fn round_trip_keeps_links() {
+        let nn = network();
+        let mut dense = DenseNetwork::from_network(&nn).unwrap();
+        dense.layers[0].bias[2] = 0.75;
+        dense.layers[2].weights.set(1, 4, -1.5);
+
+        let mut written = nn.clone();
+        dense.write_to(&mut written);
+        assert_eq!(written.layers[1].neurons[2].bias, 0.75);
+        assert_eq!(written.layers[3].neurons[1].input_links[4].weight, -1.5);
+        assert_eq!(written.layers[2].neurons[3].input_links.len(), 1);
+        assert_eq!(written.layers[2].neurons[3].input_links[0].weight, nn.layers[2].neurons[3].input_links[0].weight);
+    }
+}
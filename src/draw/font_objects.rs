@@ -8,7 +8,7 @@ pub struct TextStyles {
 }
 
 impl TextStyles {
-    pub fn neuron_header(&self) -> TextParams {
+    pub fn neuron_header(&self) -> TextParams<'_> {
         TextParams {
             font: Some(&self.font),
             font_size: 16,
@@ -17,7 +17,7 @@ impl TextStyles {
         }
     }
 
-    pub fn neuron_error(&self) -> TextParams {
+    pub fn neuron_error(&self) -> TextParams<'_> {
         TextParams {
             font: Some(&self.font),
             font_size: 16,
@@ -26,7 +26,7 @@ impl TextStyles {
         }
     }
 
-    pub fn link_weight(&self) -> TextParams {
+    pub fn link_weight(&self) -> TextParams<'_> {
         TextParams {
             font: Some(&self.font),
             font_size: 16,
@@ -34,7 +34,7 @@ impl TextStyles {
             ..Default::default()
         }
     }
-    pub fn button(&self) -> TextParams {
+    pub fn button(&self) -> TextParams<'_> {
         TextParams {
             font: Some(&self.font),
             font_size: 24,
@@ -1,5 +1,5 @@
 use crate::draw::font_objects::TextStyles;
-use crate::draw::objects::{Arrow, COLOUR_BACKGROUND, COLOUR_CIRCLE, COLOUR_LINK, Model, NCircle, Point, PositioningView, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::draw::objects::{Arrow, COLOUR_BACKGROUND, COLOUR_CIRCLE, COLOUR_HIGHLIGHT, COLOUR_LINK, Model, NCircle, Point, PositioningView, WINDOW_HEIGHT, WINDOW_WIDTH};
 use macroquad::prelude::*;
 use std::sync::mpsc::{Receiver, Sender};
 use std::thread;
@@ -24,6 +24,8 @@ pub(crate) fn spawn_ui_thread(view: PositioningView, rx: Receiver<Model>, tx: Se
                 let text_styles = TextStyles { font };
                 let mut model : Option<Model> = None;
                 let iteration_point = Point{x: 100.0, y: 20.0};
+                let learning_rate_point = Point{x: 250.0, y: 20.0};
+                let message_point = Point{x: WINDOW_WIDTH as f32 / 2.0, y: 50.0};
                 let point = Point { x: WINDOW_WIDTH as f32 / 2.0, y: WINDOW_HEIGHT as f32 - 100.0 };
                 let mut pause_button = Button::new("PAUSE".to_string(), point.clone(), &text_styles);
 
@@ -40,10 +42,16 @@ pub(crate) fn spawn_ui_thread(view: PositioningView, rx: Receiver<Model>, tx: Se
                         model = Some(new_msg);
                     }
                     if let Some(model) = model.as_ref() {
-                        draw_values(&view, &model,  &text_styles);
+                        draw_highlight(&view, model);
+                        draw_values(&view, model, &text_styles);
+                        if let Some(message) = model.message.as_ref() {
+                            draw_text_center(message, &message_point, text_styles.neuron_header());
+                        }
 
                         let iteration = format!("{}", model.iterations);
                         draw_text_center(&iteration, &iteration_point, text_styles.neuron_error());
+                        let learning_rate = format!("lr: {:.6}", model.learning_rate);
+                        draw_text_center(&learning_rate, &learning_rate_point, text_styles.neuron_error());
                         pause_button.active = model.button_pause_active;
                         stepping_button.active = model.button_stepping_active;
                         play_button.active = model.button_play_active;
@@ -55,13 +63,13 @@ pub(crate) fn spawn_ui_thread(view: PositioningView, rx: Receiver<Model>, tx: Se
                     let mouse_pos = mouse_position().into();
                     if is_mouse_button_pressed(MouseButton::Left) {
                         if pause_button.is_clicked(mouse_pos) {
-                            tx.send(Events::PauseRequested).unwrap();;
+                            tx.send(Events::PauseRequested).unwrap();
                         }
                         if stepping_button.is_clicked(mouse_pos) {
-                            tx.send(Events::SteppingRequested).unwrap();;
+                            tx.send(Events::SteppingRequested).unwrap();
                         }
                         if play_button.is_clicked(mouse_pos) {
-                            tx.send(Events::PlayRequested).unwrap();;
+                            tx.send(Events::PlayRequested).unwrap();
                         }
                     }
                     next_frame().await;
@@ -76,8 +84,15 @@ fn draw_values(view: &PositioningView, model: &Model, text_style: &TextStyles) {
         if let Some(circle) = view.circles.iter().find(|c| c.id == circle_value.id) {
             let error = format!("err: {:.5}", circle_value.error);
             draw_text_center(&error, &circle.center, text_style.neuron_error());
-            let output = format!("out: {:.2}", circle_value.value);
-            draw_text_center(&output, &circle.output, text_style.neuron_header())
+            let output = match circle_value.raw {
+                Some(raw) => format!("out: {:.2} ({:.2})", circle_value.value, raw),
+                None => format!("out: {:.2}", circle_value.value),
+            };
+            draw_text_center(&output, &circle.output, text_style.neuron_header());
+            if let Some(bias) = circle_value.bias {
+                let bias = format!("b: {:.3}", bias);
+                draw_text_center(&bias, &circle.bias, text_style.link_weight());
+            }
         }
     }
     for link_value in model.link_values.iter() {
@@ -88,6 +103,16 @@ fn draw_values(view: &PositioningView, model: &Model, text_style: &TextStyles) {
     }
 }
 
+fn draw_highlight(view: &PositioningView, model: &Model) {
+    let color = Color::from_hex(COLOUR_HIGHLIGHT);
+    if let Some(circle) = view.circles.iter().find(|c| Some(&c.id) == model.highlighted_neuron.as_ref()) {
+        draw_circle_lines(circle.center.x, circle.center.y, circle.radius + 3.0, 4.0, color);
+    }
+    if let Some(arrow) = view.arrows.iter().find(|a| Some(&a.id) == model.highlighted_link.as_ref()) {
+        draw_arrow(arrow, color);
+    }
+}
+
 pub fn draw_text_center(text: &str, point: &Point, text_params: TextParams) {
     // Measure the text size
     let dims = measure_text(text, text_params.font, text_params.font_size, 1.0);
@@ -118,28 +143,27 @@ fn draw_background(view: &PositioningView, text_style: &TextStyles) {
         draw_neuron_circle(circle, text_style);
     }
     for arrow in view.arrows.iter() {
-        draw_arrow(arrow);
+        draw_arrow(arrow, Color::from_hex(COLOUR_LINK));
     }
 }
 fn draw_neuron_circle(circle: &NCircle, text_style: &TextStyles) {
     draw_circle(
-        circle.center.x as f32,
-        circle.center.y as f32,
-        circle.radius as f32,
+        circle.center.x,
+        circle.center.y,
+        circle.radius,
         Color::from_hex(COLOUR_CIRCLE),
     );
 
     draw_circle(
-        circle.center.x as f32,
-        circle.center.y as f32,
-        (circle.radius - 2.0) as f32,
+        circle.center.x,
+        circle.center.y,
+        circle.radius - 2.0,
         Color::from_hex(COLOUR_BACKGROUND),
     );
     draw_text_center(&circle.caption_text, &circle.caption, text_style.neuron_header());
 }
 
-fn draw_arrow(arrow: &Arrow) {
-    let color: Color = Color::from_hex(COLOUR_LINK);
+fn draw_arrow(arrow: &Arrow, color: Color) {
     draw_line(
         arrow.from.x,
         arrow.from.y,
@@ -171,6 +195,7 @@ mod tests {
     use crate::draw::macroquad_draw::spawn_ui_thread;
     use crate::draw::objects::{LValue, Model, NValue};
     use crate::draw::view::build_view;
+    use crate::execution_objects::Events;
     use crate::nn_build::build_nn;
     use rand::Rng;
     use std::sync::mpsc;
@@ -180,7 +205,7 @@ mod tests {
     #[test]
     #[ignore]
     fn draw_test() {
-        let nn = build_nn();
+        let nn = build_nn(&mut rand::rng());
         let view = build_view(&nn);
         let mut neuron_values: Vec<NValue> = vec![];
         let mut link_values: Vec<LValue> = vec![];
@@ -189,9 +214,10 @@ mod tests {
         for n in view.circles.iter() {
             neuron_values.push(NValue {
                 id: n.id.clone(),
-                input: rng.random(),
                 value: rng.random(),
                 error: rng.random(),
+                bias: Some(rng.random()),
+                raw: None,
             })
         }
         for l in view.arrows.iter() {
@@ -202,9 +228,21 @@ mod tests {
         }
 
         let (tx, rx) = mpsc::channel::<Model>();
-        let join_handle = spawn_ui_thread(view, rx);
+        let (tx_events, _rx_events) = mpsc::channel::<Events>();
+        let join_handle = spawn_ui_thread(view, rx, tx_events);
         sleep(Duration::from_secs(3));
-        tx.send(Model { neuron_values, link_values, iterations: 0 }).unwrap();
+        tx.send(Model {
+            neuron_values,
+            link_values,
+            iterations: 0,
+            learning_rate: 0.01,
+            button_pause_active: true,
+            button_stepping_active: false,
+            button_play_active: false,
+            highlighted_neuron: Some("m1".to_string()),
+            highlighted_link: None,
+            message: Some("diverged: output of m1 is NaN".to_string()),
+        }).unwrap();
         
         join_handle.join().unwrap();
     }
@@ -4,6 +4,7 @@ pub const COLOUR_BACKGROUND: u32 = 0x1e1f22;
 pub const COLOUR_CIRCLE: u32 = 0xce7b47;
 pub const COLOUR_LINK: u32 = 0xb7babf;
 pub const COLOUR_ERROR: u32 = 0xc37ab6;
+pub const COLOUR_HIGHLIGHT: u32 = 0xe05555;
 
 #[derive(Debug, Clone)]
 pub struct Point {
@@ -15,6 +16,7 @@ pub struct NCircle {
     pub id: String,
     pub caption: Point,
     pub caption_text: String,
+    pub bias: Point,
     pub center: Point,    
     pub output: Point,
     pub radius: f32,
@@ -31,6 +33,10 @@ impl NCircle {
                 x,
                 y: y - radius / 2.0,
             },
+            bias: Point {
+                x,
+                y: y - radius / 4.0,
+            },
             output: Point {
                 x,
                 y: y + radius / 2.0,
@@ -52,13 +58,13 @@ pub struct Arrow {
 impl Arrow {
     pub fn new(id: String, from: &NCircle, to: &NCircle) -> Self {
         // Circle A (start)
-        let x1: f32 = from.center.x as f32;
-        let y1: f32 = from.center.y as f32;
-        let r1: f32 = from.radius as f32;
+        let x1: f32 = from.center.x;
+        let y1: f32 = from.center.y;
+        let r1: f32 = from.radius;
         // Circle B (end)
-        let x2: f32 = to.center.x as f32;
-        let y2: f32 = to.center.y as f32;
-        let r2: f32 = to.radius as f32;
+        let x2: f32 = to.center.x;
+        let y2: f32 = to.center.y;
+        let r2: f32 = to.radius;
 
         // Direction vector from A to B
         let dx = x2 - x1;
@@ -109,7 +115,7 @@ impl Arrow {
         }
     }
     
-    pub fn generate_id(from: &String, to: &String) -> String {
+    pub fn generate_id(from: &str, to: &str) -> String {
         format!("{}->{}", from, to)
     }
 }
@@ -121,9 +127,12 @@ pub struct PositioningView {
 
 pub struct NValue {
     pub id: String,
-    pub input: f32,
     pub value: f32,
-    pub error: f32    
+    pub error: f32,
+    /// None for input neurons
+    pub bias: Option<f32>,
+    /// Value in the original units, for input and output neurons of a normalized network
+    pub raw: Option<f32>,
 }
 pub struct LValue {
     pub id: String,
@@ -133,8 +142,13 @@ pub struct Model {
     pub neuron_values: Vec<NValue>,
     pub link_values: Vec<LValue>,
     pub iterations: usize,
+    pub learning_rate: f32,
     pub button_pause_active: bool,
     pub button_stepping_active: bool,
     pub button_play_active: bool,
+    /// Neuron and link (arrow id) where the run diverged
+    pub highlighted_neuron: Option<String>,
+    pub highlighted_link: Option<String>,
+    pub message: Option<String>,
 }
 
@@ -1,23 +1,33 @@
-use std::cmp::PartialEq;
 use std::ops::Sub;
 use std::sync::mpsc::Sender;
 use std::time::{Duration, Instant};
-use crate::draw::objects::{LValue, Model, NValue};
+use crate::draw::objects::{Arrow, LValue, Model, NValue};
 use crate::execution_objects::{ExecutionObjects, RunMode};
 use crate::nn_objects::Network;
 
 const FRAME_RATE: Duration = Duration::from_millis(1000 / 20);
 pub struct DrawAdapter {
-    tx: Sender<Model>,
+    /// None in headless mode, frames are dropped
+    tx: Option<Sender<Model>>,
     last_sent: Instant,
+    /// Input and output values in the original units next to the normalized ones
+    show_raw: bool,
 }
 
 
 impl DrawAdapter {
-    pub fn new(tx: Sender<Model>) -> Self {
-        Self { tx , last_sent: Instant::now().sub(FRAME_RATE) }
+    pub fn new(tx: Sender<Model>, show_raw: bool) -> Self {
+        Self { tx: Some(tx), last_sent: Instant::now().sub(FRAME_RATE), show_raw }
+    }
+
+    pub fn headless() -> Self {
+        Self { tx: None, last_sent: Instant::now(), show_raw: false }
     }
     
+    pub fn has_window(&self) -> bool {
+        self.tx.is_some()
+    }
+
     pub fn send_timed(&mut self, nn: &Network, env: &ExecutionObjects) {
         if self.last_sent.elapsed() >= FRAME_RATE {
             self.send(nn, env);
@@ -25,35 +35,47 @@ impl DrawAdapter {
     }
     
     pub fn send(&mut self, nn: &Network, env: &ExecutionObjects) {
+        let Some(tx) = self.tx.as_ref() else {
+            return;
+        };
         let mut neuron_values: Vec<NValue> = vec![];
         let mut link_values: Vec<LValue> = vec![];
-        for layer in nn.layers.iter() {
-            for n in layer.neurons.iter().filter(|n| !n.is_dummy()) {
+        let show_raw = self.show_raw && nn.normalization.is_some();
+        for (index, layer) in nn.layers.iter().enumerate() {
+            let outputs: Vec<f32> = layer.neurons.iter().map(|n| n.output).collect();
+            let raw_values = match index {
+                _ if !show_raw => vec![],
+                0 => nn.denormalize_inputs(&outputs),
+                _ if index == nn.layers_count - 1 => nn.denormalize_outputs(&outputs),
+                _ => vec![],
+            };
+            for (neuron_index, n) in layer.neurons.iter().enumerate() {
                 neuron_values.push(NValue {
                     id: n.id.clone(),
-                    input: n.sum_input,
                     value: n.output,
                     error: n.error,
+                    bias: if n.input_links.is_empty() { None } else { Some(n.bias) },
+                    raw: raw_values.get(neuron_index).copied(),
                 });
-                for l in n.input_links.iter().filter(|l| !l.is_dummy()) {
+                for l in n.input_links.iter() {
                     link_values.push(LValue {
-                        id: generate_id(&l.source_id, &n.id),
+                        id: Arrow::generate_id(&l.source_id, &n.id),
                         value: l.weight,
                     })
                 }
             }        
         }
 
-        self.tx.send(Model { neuron_values, link_values, 
+        tx.send(Model { neuron_values, link_values, 
             iterations: env.iteration,
+            learning_rate: env.learning_rate,
             button_pause_active : env.run_mode==RunMode::Pause,
             button_stepping_active: env.run_mode==RunMode::Stepping,
             button_play_active: env.run_mode==RunMode::Running,
+            highlighted_neuron: env.non_finite.as_ref().map(|p| p.neuron().to_string()),
+            highlighted_link: env.non_finite.as_ref().and_then(|p| p.link()).map(|(from, to)| Arrow::generate_id(from, to)),
+            message: env.non_finite.as_ref().map(|p| format!("diverged: {p}")),
         }).unwrap();
         self.last_sent = Instant::now()
     }
 }
-
-fn generate_id(from: &String, to: &String) -> String {
-    format!("{}->{}", from, to)
-}
\ No newline at end of file
@@ -0,0 +1,670 @@
+use crate::checkpoint::Checkpoint;
+use crate::dense::{DenseNetwork, Matrix};
+use crate::draw_adapter::DrawAdapter;
+use crate::execution_objects::{EpochRecord, Events, ExecutionObjects, Metrics, RunMode, TrainProgress};
+use crate::loss_functions::Loss;
+use crate::lr_schedules::LrSchedule;
+use crate::nan_guard::{find_non_finite, Divergence, NonFinite};
+use crate::nn_objects::Network;
+use crate::optimizers::Optimizer;
+use crate::dataset::Sample;
+use crate::{backpropagation, lr_schedules, optimizers};
+use rand_chacha::ChaCha8Rng;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+const STEPPING_DURATION: Duration = Duration::from_millis(1000);
+/// A run that keeps diverging after this many rollbacks is stopped
+const MAX_ROLLBACKS: usize = 10;
+
+pub struct ExecutionContext {
+    pub nn: Network,
+    pub iteration: usize,
+    pub error: f32,
+    /// Samples per weight update: 1 - stochastic, train set size - full batch
+    pub batch_size: usize,
+    pub batch_samples: usize,
+    pub gradients: Vec<f32>,
+    pub schedule: Box<dyn LrSchedule>,
+    pub loss: Box<dyn Loss>,
+    pub optimizer: Box<dyn Optimizer>,
+    /// Dropout masks
+    pub rng: ChaCha8Rng,
+    /// `rng` before the current sample, while the sample is half done
+    pub sample_start_rng: Option<ChaCha8Rng>,
+    pub progress: TrainProgress,
+    /// Written on pause, periodic checkpoints are up to the caller
+    pub checkpoint_path: Option<PathBuf>,
+    /// Trains with the dense backend instead of `nn`, which is updated at the end of every epoch.
+    /// No UI stepping
+    pub dense: Option<DenseNetwork>,
+    /// On a NaN or infinite value: roll back to the checkpoint and multiply the learning rate by this,
+    /// the run fails when None
+    pub rollback_factor: Option<f32>,
+    pub rollbacks: usize,
+    /// Last step that produced a non-finite value, highlighted in the UI
+    pub divergence: Option<Divergence>,
+    pub run_mode: RunMode,
+    pub tx_adapter: DrawAdapter,
+    pub rx_events: Receiver<Events>,
+}
+
+impl ExecutionContext {
+    /// Trains the samples of the epoch not trained yet (all of them unless resumed from a checkpoint).
+    /// Returns the mean sample error, None when the run diverged and was rolled back:
+    /// the epoch to train is in `progress` again
+    pub fn train_epoch(&mut self, items: &[Sample]) -> Result<Option<f32>, Box<dyn std::error::Error>> {
+        if let Some(mut dense) = self.dense.take() {
+            let result = self.train_epoch_dense(&mut dense, items);
+            match result {
+                Ok(None) => dense = DenseNetwork::from_network(&self.nn)?,
+                _ => dense.write_to(&mut self.nn),
+            }
+            self.dense = Some(dense);
+            return result;
+        }
+        for item in items.iter().skip(self.progress.sample) {
+            if !self.train_loop(item)? {
+                return Ok(None);
+            }
+        }
+        self.finish_batch();
+        Ok(Some(self.progress.epoch_error / items.len().max(1) as f32))
+    }
+
+    /// Same as `train_epoch`, one matrix pass per mini-batch.
+    /// A mini-batch started before a checkpoint (UI pause, graph backend) is completed by the first batch
+    fn train_epoch_dense(&mut self, dense: &mut DenseNetwork, items: &[Sample]) -> Result<Option<f32>, Box<dyn std::error::Error>> {
+        if self.progress.sample >= items.len() && self.batch_samples > 0 {
+            //примеры эпохи пройдены, осталось применить накопленные градиенты
+            self.finish_batch();
+            *dense = DenseNetwork::from_network(&self.nn)?;
+        }
+        while self.progress.sample < items.len() {
+            let start = self.progress.sample;
+            let size = self.batch_size.max(1).saturating_sub(self.batch_samples).max(1);
+            let batch = &items[start..(start + size).min(items.len())];
+            let outputs_count = dense.layers.last().map_or(0, |l| l.bias.len());
+            if let Some(item) = batch.iter().find(|i| i.inputs.len() < dense.inputs || i.outputs.len() < outputs_count) {
+                return Err(format!("network has {} inputs and {outputs_count} outputs, train item {} and {}",
+                                   dense.inputs, item.inputs.len(), item.outputs.len()).into());
+            }
+            let inputs: Vec<Vec<f32>> = batch.iter().map(|i| self.nn.normalize_inputs(&i.inputs)).collect();
+            let activations = dense.forward(Matrix::from_rows(dense.inputs, inputs.iter().map(|i| i.as_slice())));
+            let outputs = activations.outputs.last().unwrap();
+            let mut errors = Matrix::zeros(outputs.rows, outputs.cols);
+            for (row, item) in batch.iter().enumerate() {
+                let mut error_sum = 0.0;
+                let mut targets_count = 0;
+                for (col, &target) in self.nn.normalize_targets(&item.outputs).iter().take(outputs.cols).enumerate() {
+                    if target.is_nan() {
+                        continue;
+                    }
+                    let output = outputs.get(row, col);
+                    errors.set(row, col, self.loss.gradient(target, output));
+                    error_sum += self.loss.value(target, output);
+                    targets_count += 1;
+                }
+                self.error = error_sum / targets_count.max(1) as f32;
+                self.progress.epoch_error += self.error.abs();
+            }
+            let finite = activations.outputs.iter().chain([&errors]).all(Matrix::is_finite);
+            let mut gradients = dense.backward(&activations, errors);
+            if (!finite || gradients.iter().any(|g| !g.is_finite()))
+                && let Some((problem, sample)) = self.locate_in_batch(dense, batch, start)? {
+                self.diverged(problem, sample, &items[sample])?;
+                return Ok(None);
+            }
+            for (gradient, pending) in gradients.iter_mut().zip(&self.gradients) {
+                *gradient += pending;
+            }
+            let samples = batch.len() + self.batch_samples;
+            dense.apply_gradients(&gradients, samples, self.optimizer.as_mut(), self.schedule.learning_rate());
+            self.batch_samples = 0;
+            self.gradients.clear();
+            if !dense.is_finite() {
+                dense.write_to(&mut self.nn);
+                let problem = find_non_finite(&self.nn).ok_or("dense parameters are not finite, but the network ones are")?;
+                self.diverged(problem, start, &batch[0])?;
+                return Ok(None);
+            }
+            self.schedule.on_step();
+            self.iteration += batch.len();
+            self.progress.sample += batch.len();
+        }
+        Ok(Some(self.progress.epoch_error / items.len().max(1) as f32))
+    }
+
+    /// Replays the batch sample by sample through `nn` with the parameters of the dense pass
+    /// to find the output or error that stopped being finite and the sample it came from.
+    /// None when only the summed gradients overflowed, then the update is caught by the parameter check
+    fn locate_in_batch(&mut self, dense: &DenseNetwork, batch: &[Sample], start: usize)
+                       -> Result<Option<(NonFinite, usize)>, Box<dyn std::error::Error>> {
+        dense.write_to(&mut self.nn);
+        for (offset, item) in batch.iter().enumerate() {
+            self.set_inputs(item)?;
+            backpropagation::forward(&mut self.nn)?;
+            self.set_output_errors(item)?;
+            backpropagation::backward(&mut self.nn)?;
+            if let Some(problem) = find_non_finite(&self.nn) {
+                return Ok(Some((problem, start + offset)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Records the epoch and moves to the next one
+    pub fn end_epoch(&mut self, train_error: f32, validation_error: f32) {
+        self.progress.epoch += 1;
+        self.progress.history.push(EpochRecord {
+            epoch: self.progress.epoch,
+            iteration: self.iteration,
+            train_error,
+            validation_error,
+            learning_rate: self.schedule.learning_rate(),
+        });
+        self.progress.sample = 0;
+        self.progress.epoch_error = 0.0;
+        self.schedule.on_epoch_end(validation_error);
+    }
+
+    /// Returns false when the step diverged and the run was rolled back
+    pub fn train_loop(&mut self, train_item: &Sample) -> Result<bool, Box<dyn std::error::Error>> {
+        self.sample_start_rng = Some(self.rng.clone());
+        self.set_inputs(train_item)?;
+        self.forward()?;
+        self.send_state();
+        self.hang_out();
+
+        self.set_output_errors(train_item)?;
+        self.backward()?;
+        self.iteration += 1;
+        self.progress.sample += 1;
+        self.progress.epoch_error += self.error.abs();
+        self.sample_start_rng = None;
+        if self.batch_samples >= self.batch_size {
+            self.finish_batch();
+        }
+        if let Some(problem) = find_non_finite(&self.nn) {
+            self.diverged(problem, self.progress.sample - 1, train_item)?;
+            return Ok(false);
+        }
+        self.send_state();
+        self.hang_out();
+        Ok(true)
+    }
+
+    /// Records the step, pauses with the problem highlighted (when there is a window),
+    /// then rolls back to the checkpoint or fails
+    fn diverged(&mut self, problem: NonFinite, sample: usize, item: &Sample) -> Result<(), Box<dyn std::error::Error>> {
+        let divergence = Divergence { problem, epoch: self.progress.epoch + 1, sample, iteration: self.iteration, item: item.clone() };
+        self.divergence = Some(divergence.clone());
+        if self.tx_adapter.has_window() {
+            //контрольную точку не пишем: в ней была бы испорченная сеть
+            self.run_mode = RunMode::Pause;
+            self.send_state_immidiately();
+            self.hang_out();
+        }
+        let Some(factor) = self.rollback_factor else {
+            return Err(format!("training diverged: {divergence}").into());
+        };
+        if self.rollbacks >= MAX_ROLLBACKS {
+            return Err(format!("training diverged after {MAX_ROLLBACKS} rollbacks: {divergence}").into());
+        }
+        let path = self.checkpoint_path.clone().ok_or("training diverged, no checkpoint to roll back to")?;
+        let mut checkpoint = Checkpoint::load(&path)?;
+        checkpoint.schedule.base_rate *= factor;
+        checkpoint.schedule.rate *= factor;
+        self.restore(checkpoint);
+        self.rollbacks += 1;
+        //следующий откат должен уменьшить уже уменьшенную скорость
+        self.save_checkpoint()
+    }
+
+    /// Runs the items forward only, weights stay untouched
+    pub fn evaluate(&mut self, items: &[Sample]) -> Result<Metrics, Box<dyn std::error::Error>> {
+        evaluate(&mut self.nn, self.loss.as_ref(), items)
+    }
+
+    /// Errors of the output layer after a forward pass, `self.error` is the sample error
+    fn set_output_errors(&mut self, item: &Sample) -> Result<(), Box<dyn std::error::Error>> {
+        let targets = self.nn.normalize_targets(&item.outputs);
+        let output_layer = &mut self.nn.layers[self.nn.layers_count - 1];
+        if output_layer.neurons.len() > targets.len() {
+            return Err(format!("network has {} outputs, train item only {}", output_layer.neurons.len(), targets.len()).into());
+        }
+        //ошибка по каждому выходу, общая ошибка - среднее; выходы без цели (NaN) не учатся
+        let mut error_sum = 0.0;
+        let mut targets_count = 0;
+        for (neuron, &target) in output_layer.neurons.iter_mut().zip(&targets) {
+            if target.is_nan() {
+                neuron.error = 0.0;
+                continue;
+            }
+            neuron.error = self.loss.gradient(target, neuron.output);
+            error_sum += self.loss.value(target, neuron.output);
+            targets_count += 1;
+        }
+        self.error = error_sum / targets_count.max(1) as f32;
+        Ok(())
+    }
+
+    fn set_inputs(&mut self, item: &Sample) -> Result<(), Box<dyn std::error::Error>> {
+        set_inputs(&mut self.nn, &item.inputs)
+    }
+
+    fn forward(&mut self) -> Result<(), String> {
+        backpropagation::sample_dropout(&mut self.nn, &mut self.rng);
+        backpropagation::forward(&mut self.nn)
+    }
+
+    fn backward(&mut self) -> Result<(), String> {
+        backpropagation::backward(&mut self.nn)?;
+        backpropagation::accumulate_gradients(&mut self.nn, &mut self.gradients)?;
+        self.batch_samples += 1;
+        Ok(())
+    }
+
+    /// Updates weights with the gradients accumulated since the previous update
+    pub fn finish_batch(&mut self) {
+        if self.batch_samples == 0 {
+            return;
+        }
+        let learning_rate = self.schedule.learning_rate();
+        backpropagation::apply_gradients(&mut self.nn, &mut self.gradients, self.batch_samples,
+                                         self.optimizer.as_mut(), learning_rate);
+        self.batch_samples = 0;
+        self.schedule.on_step();
+    }
+
+    /// A half done sample is not saved, it is trained again after resume
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            network: self.nn.clone(),
+            iteration: self.iteration,
+            progress: self.progress.clone(),
+            schedule: self.schedule.state(),
+            optimizer: self.optimizer.state(),
+            rng: self.sample_start_rng.clone().unwrap_or_else(|| self.rng.clone()),
+            batch_samples: self.batch_samples,
+            gradients: self.gradients.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.nn = checkpoint.network;
+        self.iteration = checkpoint.iteration;
+        self.progress = checkpoint.progress;
+        self.schedule = lr_schedules::build(checkpoint.schedule);
+        self.optimizer = optimizers::build(checkpoint.optimizer);
+        self.rng = checkpoint.rng;
+        self.sample_start_rng = None;
+        self.batch_samples = checkpoint.batch_samples;
+        self.gradients = checkpoint.gradients;
+    }
+
+    pub fn save_checkpoint(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = &self.checkpoint_path {
+            self.checkpoint().save(path)?;
+        }
+        Ok(())
+    }
+
+    fn pause(&mut self) {
+        self.run_mode = RunMode::Pause;
+        self.send_state_immidiately();
+        if let Err(e) = self.save_checkpoint() {
+            eprintln!("checkpoint is not saved: {e}");
+        }
+    }
+
+    fn send_state(&mut self) {
+        let execution_objects = ExecutionObjects {
+            iteration: self.iteration,
+            learning_rate: self.schedule.learning_rate(),
+            run_mode: self.run_mode,
+            non_finite: self.divergence.as_ref().map(|d| d.problem.clone()),
+        };
+        self.tx_adapter.send_timed(&self.nn, &execution_objects);
+    }
+
+    pub fn send_state_immidiately(&mut self) {
+        let execution_objects = ExecutionObjects {
+            iteration: self.iteration,
+            learning_rate: self.schedule.learning_rate(),
+            run_mode: self.run_mode,
+            non_finite: self.divergence.as_ref().map(|d| d.problem.clone()),
+        };
+        self.tx_adapter.send(&self.nn, &execution_objects);
+    }
+
+    fn hang_out(&mut self) {
+        if self.run_mode == RunMode::Stepping || self.run_mode == RunMode::Pause {
+            loop {
+                if let Ok(event) = self.rx_events.recv_timeout(STEPPING_DURATION) {
+                    match event {
+                        Events::PauseRequested => {
+                            if self.run_mode == RunMode::Pause {
+                                self.send_state_immidiately();
+                            } else {
+                                self.pause();
+                            }
+                            break;
+                        },
+                        Events::SteppingRequested => {
+                            self.run_mode = RunMode::Stepping;
+                            break;
+                        }
+                        Events::PlayRequested => {
+                            self.run_mode = RunMode::Running;
+                            break;
+                        }
+                    }
+                } else {
+                    if self.run_mode == RunMode::Stepping {
+                        break;
+                    }
+                }
+            }
+        }
+        if self.run_mode == RunMode::Running && let Ok(event) = self.rx_events.try_recv() {
+            match event {
+                Events::PauseRequested => self.pause(),
+                Events::SteppingRequested => self.run_mode = RunMode::Stepping,
+                Events::PlayRequested => self.run_mode = RunMode::Running,
+            }
+        }
+    }
+
+}
+
+/// `inputs` in the original units, the network normalizes them
+pub fn set_inputs(nn: &mut Network, inputs: &[f32]) -> Result<(), Box<dyn std::error::Error>> {
+    if nn.layers[0].neurons.len() > inputs.len() {
+        return Err(format!("network has {} inputs, got only {}", nn.layers[0].neurons.len(), inputs.len()).into());
+    }
+    let inputs = nn.normalize_inputs(inputs);
+    for (neuron, value) in nn.layers[0].neurons.iter_mut().zip(&inputs) {
+        neuron.output = *value;
+    }
+    Ok(())
+}
+
+/// Runs the items forward only, weights stay untouched.
+/// The loss is computed on the normalized outputs, like while training, the absolute errors in the original units.
+/// Outputs without a target (NaN) are not counted
+pub fn evaluate(nn: &mut Network, loss: &dyn Loss, items: &[Sample]) -> Result<Metrics, Box<dyn std::error::Error>> {
+    let mut loss_sum = 0.0;
+    let mut absolute_error_sum = 0.0;
+    let mut max_absolute_error: f32 = 0.0;
+    let mut outputs_count = 0;
+    backpropagation::clear_dropout(nn);
+    for item in items {
+        set_inputs(nn, &item.inputs)?;
+        backpropagation::forward(nn)?;
+        if nn.last().neurons.len() > item.outputs.len() {
+            return Err(format!("network has {} outputs, item only {}", nn.last().neurons.len(), item.outputs.len()).into());
+        }
+        let targets = nn.normalize_targets(&item.outputs);
+        let predictions = nn.predictions();
+        for (index, neuron) in nn.last().neurons.iter().enumerate().filter(|(index, _)| !targets[*index].is_nan()) {
+            let absolute_error = (item.outputs[index] - predictions[index]).abs();
+            loss_sum += loss.value(targets[index], neuron.output);
+            absolute_error_sum += absolute_error;
+            max_absolute_error = max_absolute_error.max(absolute_error);
+            outputs_count += 1;
+        }
+    }
+    let count = outputs_count.max(1) as f32;
+    Ok(Metrics {
+        loss: loss_sum / count,
+        mean_absolute_error: absolute_error_sum / count,
+        max_absolute_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw_adapter::DrawAdapter;
+    use crate::execution_context::{set_inputs, ExecutionContext};
+    use crate::execution_objects::RunMode;
+    use crate::checkpoint::Checkpoint;
+    use crate::dense::DenseNetwork;
+    use crate::lr_schedules::{LrScheduleFunction, LrScheduleState};
+    use crate::nn_objects::LossFunction;
+    use crate::optimizers::{OptimizerFunction, OptimizerState};
+    use crate::topology::TopologySpec;
+    use crate::execution_objects::TrainProgress;
+    use crate::nn_objects::Network;
+    use crate::nan_guard::NonFinite;
+    use crate::normalization::{Normalization, Scaling};
+    use crate::dataset::Sample;
+    use crate::generators::{generate, GeneratorConfig};
+    use crate::train_data::split;
+    use crate::{backpropagation, loss_functions, lr_schedules, optimizers};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    const TOPOLOGY: &str = r#"{
+        "inputs": ["k", "x", "b"],
+        "layers": [
+            {"neurons": {"size": 8, "prefix": "h"}, "activation": "Relu", "initializer": "HeUniform", "dropout": 0.25},
+            {"neurons": ["y"], "activation": "Linear", "initializer": "XavierNormal"}
+        ]
+    }"#;
+
+    /// Same setup as `main::train`
+    fn context(seed: u64) -> (ExecutionContext, Vec<Sample>) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let nn = TopologySpec::from_json(TOPOLOGY).unwrap().build(&mut rng).unwrap();
+        let data = split(generate(&GeneratorConfig::default(), &mut rng).unwrap().samples, 0.15, 0.15, &mut rng).unwrap();
+        let (_, rx_events) = mpsc::channel();
+        let execution = ExecutionContext {
+            nn,
+            iteration: 0,
+            error: 1.0,
+            batch_size: 4,
+            batch_samples: 0,
+            gradients: vec![],
+            schedule: lr_schedules::build(LrScheduleState::new(LrScheduleFunction::Constant, 0.001)),
+            loss: loss_functions::build(&LossFunction::Mse),
+            optimizer: optimizers::build(OptimizerState::new("adam".parse::<OptimizerFunction>().unwrap())),
+            rng,
+            sample_start_rng: None,
+            progress: TrainProgress::default(),
+            checkpoint_path: None,
+            dense: None,
+            rollback_factor: None,
+            rollbacks: 0,
+            divergence: None,
+            run_mode: RunMode::Running,
+            tx_adapter: DrawAdapter::headless(),
+            rx_events,
+        };
+        (execution, data.train)
+    }
+
+    fn train_epochs(execution: &mut ExecutionContext, items: &[Sample], epochs: usize) {
+        for _ in 0..epochs {
+            let train_error = execution.train_epoch(items).unwrap().unwrap();
+            execution.end_epoch(train_error, train_error);
+        }
+    }
+
+    fn weights(nn: &Network) -> Vec<f32> {
+        nn.layers.iter()
+            .flat_map(|l| l.neurons.iter())
+            .flat_map(|n| n.input_links.iter().map(|l| l.weight).chain([n.bias]))
+            .collect()
+    }
+
+    fn train_weights(seed: u64) -> Vec<f32> {
+        let (mut execution, items) = context(seed);
+        train_epochs(&mut execution, &items, 3);
+        weights(&execution.nn)
+    }
+
+    #[test]
+    fn same_seed_same_weights() {
+        let first = train_weights(42);
+        let second = train_weights(42);
+        assert_eq!(first.iter().map(|w| w.to_bits()).collect::<Vec<_>>(),
+                   second.iter().map(|w| w.to_bits()).collect::<Vec<_>>());
+        assert_ne!(first, train_weights(43));
+    }
+
+    #[test]
+    fn normalized_network_answers_in_original_units() {
+        let (mut execution, items) = context(5);
+        execution.nn.normalization = Some(Normalization::fit(&items, Scaling::MinMax, Scaling::ZScore));
+        let before = execution.evaluate(&items).unwrap();
+        train_epochs(&mut execution, &items, 20);
+        let after = execution.evaluate(&items).unwrap();
+        assert!(after.loss < before.loss, "{before:?} -> {after:?}");
+
+        let nn = &mut execution.nn;
+        let mut absolute_error_sum = 0.0;
+        for item in items.iter() {
+            set_inputs(nn, &item.inputs).unwrap();
+            assert!(nn.layers[0].neurons.iter().all(|n| (0.0..=1.0).contains(&n.output)));
+            backpropagation::forward(nn).unwrap();
+            absolute_error_sum += (item.outputs[0] - nn.predictions()[0]).abs();
+        }
+        let mean_absolute_error = absolute_error_sum / items.len() as f32;
+        assert!((mean_absolute_error - after.mean_absolute_error).abs() < 1e-3 * mean_absolute_error);
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let (mut continuous, items) = context(7);
+        train_epochs(&mut continuous, &items, 3);
+
+        let (mut first, _) = context(7);
+        train_epochs(&mut first, &items, 1);
+        //останавливаемся посреди эпохи и посреди мини-батча
+        for item in items.iter().take(6) {
+            first.train_loop(item).unwrap();
+        }
+        let json = serde_json::to_string(&first.checkpoint()).unwrap();
+        let checkpoint: Checkpoint = serde_json::from_str(&json).unwrap();
+
+        let (mut resumed, _) = context(8);
+        resumed.restore(checkpoint);
+        train_epochs(&mut resumed, &items, 2);
+
+        assert_eq!(weights(&continuous.nn), weights(&resumed.nn));
+        assert_eq!(continuous.iteration, resumed.iteration);
+        assert_eq!(resumed.progress.history.len(), 3);
+    }
+
+    /// File in the temp directory, unique per process and test, removed when the test ends or fails
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            TempFile(std::env::temp_dir().join(format!("square-eq-nn-{}-{name}", std::process::id())))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn rollback_on_divergence() {
+        let (mut execution, items) = context(3);
+        let checkpoint = TempFile::new("rollback.checkpoint.json");
+        execution.checkpoint_path = Some(checkpoint.0.clone());
+        execution.rollback_factor = Some(0.5);
+        train_epochs(&mut execution, &items, 1);
+        execution.save_checkpoint().unwrap();
+        let saved = weights(&execution.nn);
+
+        execution.nn.layers[2].neurons[0].input_links[0].weight = f32::INFINITY;
+        assert!(execution.train_epoch(&items).unwrap().is_none());
+        let divergence = execution.divergence.clone().unwrap();
+        assert_eq!(divergence.problem.neuron(), "y");
+        assert_eq!((divergence.epoch, divergence.sample), (2, 0));
+        assert_eq!(divergence.item, items[0]);
+        assert_eq!(weights(&execution.nn), saved);
+        assert_eq!((execution.progress.epoch, execution.rollbacks), (1, 1));
+        assert_eq!(execution.schedule.learning_rate(), 0.0005);
+        train_epochs(&mut execution, &items, 1);
+
+        execution.rollback_factor = None;
+        execution.nn.layers[1].neurons[0].bias = f32::NAN;
+        let error = execution.train_epoch(&items).unwrap_err().to_string();
+        assert!(error.starts_with("training diverged: "), "{error}");
+    }
+
+    #[test]
+    fn dense_divergence_is_located() {
+        let (mut execution, mut items) = context(3);
+        for layer in execution.nn.layers.iter_mut() {
+            layer.dropout = 0.0;
+        }
+        execution.dense = Some(DenseNetwork::from_network(&execution.nn).unwrap());
+        items[5].inputs[1] = f32::NAN;
+        let error = execution.train_epoch(&items).unwrap_err().to_string();
+        assert!(error.contains("at epoch 1, sample 5 "), "{error}");
+        let divergence = execution.divergence.unwrap();
+        assert!(matches!(divergence.problem, NonFinite::Output { .. }), "{divergence}");
+        assert!(divergence.item.inputs[1].is_nan());
+    }
+
+    #[test]
+    fn dense_resumes_partial_batch() {
+        let without_dropout = |seed| {
+            let (mut execution, items) = context(seed);
+            for layer in execution.nn.layers.iter_mut() {
+                layer.dropout = 0.0;
+            }
+            (execution, items)
+        };
+        let (mut graph, items) = without_dropout(9);
+        train_epochs(&mut graph, &items, 2);
+
+        //пауза посреди мини-батча графового бэкенда
+        let (mut first, _) = without_dropout(9);
+        for item in items.iter().take(6) {
+            first.train_loop(item).unwrap();
+        }
+        assert_eq!(first.batch_samples, 2);
+        let (mut resumed, _) = without_dropout(10);
+        resumed.restore(first.checkpoint());
+        resumed.dense = Some(DenseNetwork::from_network(&resumed.nn).unwrap());
+        let train_error = resumed.train_epoch(&items).unwrap().unwrap();
+        resumed.end_epoch(train_error, train_error);
+        train_epochs(&mut resumed, &items, 1);
+
+        assert_eq!(graph.iteration, resumed.iteration);
+        assert_eq!(resumed.batch_samples, 0);
+        for (g, d) in weights(&graph.nn).iter().zip(weights(&resumed.nn)) {
+            assert!((g - d).abs() < 1e-3 * g.abs().max(1.0), "graph {g}, dense {d}");
+        }
+    }
+
+    #[test]
+    fn dense_backend_matches_graph() {
+        let (mut graph, items) = context(5);
+        let (mut dense, _) = context(5);
+        for execution in [&mut graph, &mut dense] {
+            for layer in execution.nn.layers.iter_mut() {
+                layer.dropout = 0.0;
+            }
+        }
+        dense.dense = Some(DenseNetwork::from_network(&dense.nn).unwrap());
+        train_epochs(&mut graph, &items, 2);
+        train_epochs(&mut dense, &items, 2);
+
+        assert_eq!(graph.iteration, dense.iteration);
+        for (g, d) in weights(&graph.nn).iter().zip(weights(&dense.nn)) {
+            assert!((g - d).abs() < 1e-3 * g.abs().max(1.0), "graph {g}, dense {d}");
+        }
+    }
+}
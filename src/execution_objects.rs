@@ -1,3 +1,6 @@
+use crate::nan_guard::NonFinite;
+use serde::{Deserialize, Serialize};
+
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunMode {
@@ -6,10 +9,44 @@ pub enum RunMode {
     Running
 }
 
+/// Result of running a data set through the network without training
+#[derive(Debug, Clone, Copy)]
+pub struct Metrics {
+    /// Mean loss per output
+    pub loss: f32,
+    pub mean_absolute_error: f32,
+    pub max_absolute_error: f32,
+}
+
+/// How far training has got, kept in checkpoints
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrainProgress {
+    /// Completed epochs
+    pub epoch: usize,
+    /// Samples of the current epoch already trained
+    pub sample: usize,
+    /// Sum of the sample errors of the current epoch
+    pub epoch_error: f32,
+    pub history: Vec<EpochRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochRecord {
+    pub epoch: usize,
+    pub iteration: usize,
+    pub train_error: f32,
+    pub validation_error: f32,
+    pub learning_rate: f32,
+}
+
 pub struct ExecutionObjects {
     pub iteration: usize,
+    pub learning_rate: f32,
     pub run_mode: RunMode,
+    /// Value that made the run diverge
+    pub non_finite: Option<NonFinite>,
 }
+#[allow(clippy::enum_variant_names)]
 pub enum Events {
     PauseRequested,
     SteppingRequested,
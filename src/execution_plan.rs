@@ -0,0 +1,178 @@
+use crate::nn_objects::Network;
+use std::collections::HashMap;
+
+/// Links of a [`Network`] resolved to neuron indices, so that the passes do not look up ids.
+/// `sources[layer][neuron][link]` is the index of the link source in the previous layer.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionPlan {
+    pub sources: Vec<Vec<Vec<usize>>>,
+}
+
+impl ExecutionPlan {
+    pub fn compile(nn: &Network) -> Result<Self, String> {
+        //у входного слоя связей нет
+        let mut sources = vec![vec![]];
+        for layers in nn.layers.windows(2) {
+            let prev_indices: HashMap<&str, usize> = layers[0].neurons.iter()
+                .enumerate()
+                .map(|(index, neuron)| (neuron.id.as_str(), index))
+                .collect();
+            let layer_sources = layers[1].neurons.iter()
+                .map(|neuron| neuron.input_links.iter()
+                    .map(|link| prev_indices.get(link.source_id.as_str()).copied()
+                        .ok_or(format!("neuron {} has a link from {}, which is not in the previous layer",
+                                       neuron.id, link.source_id)))
+                    .collect::<Result<Vec<usize>, String>>())
+                .collect::<Result<Vec<_>, String>>()?;
+            sources.push(layer_sources);
+        }
+        Ok(ExecutionPlan { sources })
+    }
+
+    /// Same number of layers, neurons and links as the network
+    pub fn matches(&self, nn: &Network) -> bool {
+        self.sources.len() == nn.layers.len()
+            && self.sources.iter().zip(nn.layers.iter()).skip(1).all(|(sources, layer)| {
+                sources.len() == layer.neurons.len()
+                    && sources.iter().zip(layer.neurons.iter()).all(|(s, n)| s.len() == n.input_links.len())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backpropagation::{backward, forward};
+    use crate::initializers::Initializer;
+    use crate::nn_objects::{ActivationFunction, Layer, Link, Network, Neuron};
+    use crate::topology::{Connectivity, LayerSpec, NeuronIds, TopologySpec};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::time::Instant;
+
+    fn wide_network(width: usize) -> Network {
+        let layer = |prefix: &str, size: usize, activation: ActivationFunction| LayerSpec {
+            neurons: NeuronIds::Generated { size, prefix: prefix.to_string() },
+            activation,
+            connectivity: Connectivity::Full,
+            initializer: Initializer::XavierUniform,
+            dropout: 0.0,
+        };
+        let spec = TopologySpec {
+            inputs: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            layers: vec![
+                layer("h", width, ActivationFunction::Sigmoid),
+                layer("g", width, ActivationFunction::Sigmoid),
+                layer("y", 2, ActivationFunction::Linear),
+            ],
+        };
+        let mut nn = spec.build(&mut StdRng::seed_from_u64(1)).unwrap();
+        for (neuron, value) in nn.layers[0].neurons.iter_mut().zip([0.3, -0.7, 0.9]) {
+            neuron.output = value;
+        }
+        nn
+    }
+
+    /// Forward and backward passes as they were before the plan: every link looks its source up by id
+    fn passes_by_id(nn: &mut Network) {
+        for layer_index in 1..nn.layers_count {
+            let (prev, current) = nn.layers.split_at_mut(layer_index);
+            for neuron in current[0].neurons.iter_mut() {
+                neuron.sum_input = neuron.input_links.iter()
+                    .map(|link| link.weight * prev[layer_index - 1].get_value(&link.source_id))
+                    .fold(neuron.bias, |acc, e| acc + e);
+                neuron.output = crate::activation_functions::apply(&neuron.function_name, neuron.sum_input);
+            }
+        }
+        for layer_index in (1..nn.layers_count).rev() {
+            let (prev, current) = nn.layers.split_at_mut(layer_index);
+            for neuron in current[0].neurons.iter_mut() {
+                neuron.delta = neuron.error * crate::activation_functions::derivative(&neuron.function_name, neuron.sum_input);
+            }
+            for prev_neuron in prev[layer_index - 1].neurons.iter_mut() {
+                prev_neuron.error = current[0].neurons.iter()
+                    .flat_map(|n| n.input_links.iter().filter(|l| l.source_id == prev_neuron.id).map(|l| l.weight * n.delta))
+                    .sum();
+            }
+        }
+    }
+
+    fn passes_by_plan(nn: &mut Network) {
+        forward(nn).unwrap();
+        backward(nn).unwrap();
+    }
+
+    fn state(nn: &Network) -> Vec<f32> {
+        nn.layers.iter().flat_map(|l| l.neurons.iter()).flat_map(|n| [n.output, n.error, n.delta]).collect()
+    }
+
+    #[test]
+    fn same_results_as_id_lookup() {
+        let mut by_id = wide_network(16);
+        let mut by_plan = by_id.clone();
+        for nn in [&mut by_id, &mut by_plan] {
+            for neuron in nn.layers[3].neurons.iter_mut() {
+                neuron.error = 0.5;
+            }
+        }
+        passes_by_id(&mut by_id);
+        passes_by_plan(&mut by_plan);
+        assert_eq!(state(&by_id), state(&by_plan));
+    }
+
+    #[test]
+    fn unknown_source_is_reported() {
+        let input = Neuron::new_input("a".to_string());
+        let output = Neuron::new_middle("y".to_string(), 0.0, ActivationFunction::Linear, vec![Link::new("q".to_string(), 1.0)]);
+        let nn = Network::new(vec![Layer::new(vec![input]), Layer::new(vec![output])]);
+        assert_eq!(super::ExecutionPlan::compile(&nn).unwrap_err(),
+                   "neuron y has a link from q, which is not in the previous layer");
+    }
+
+    #[test]
+    fn plan_follows_topology_changes() {
+        let mut nn = wide_network(4);
+        passes_by_plan(&mut nn);
+
+        //новая связь меняет количество - план пересобирается сам
+        let source = nn.layers[1].neurons[0].id.clone();
+        nn.layers[2].neurons[1].input_links.push(Link::new(source, 0.5));
+        let mut by_id = nn.clone();
+        passes_by_plan(&mut nn);
+        passes_by_id(&mut by_id);
+        assert_eq!(state(&by_id), state(&nn));
+
+        //перестановка связей количество не меняет
+        nn.layers[2].neurons[0].input_links.swap(0, 3);
+        nn.invalidate_plan();
+        let mut by_id = nn.clone();
+        passes_by_plan(&mut nn);
+        passes_by_id(&mut by_id);
+        assert_eq!(state(&by_id), state(&nn));
+
+        nn.layers[3].neurons[0].input_links[0].source_id = "q".to_string();
+        nn.invalidate_plan();
+        assert!(forward(&mut nn).unwrap_err().contains("which is not in the previous layer"));
+    }
+
+    /// cargo test --release forward_backward_speed -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn forward_backward_speed() {
+        const PASSES: u32 = 20;
+        for width in [32, 128, 256] {
+            let mut nn = wide_network(width);
+            let started = Instant::now();
+            for _ in 0..PASSES {
+                passes_by_id(&mut nn);
+            }
+            let by_id = started.elapsed() / PASSES;
+            let started = Instant::now();
+            for _ in 0..PASSES {
+                passes_by_plan(&mut nn);
+            }
+            let by_plan = started.elapsed() / PASSES;
+            println!("width {width}: by id {by_id:?}, by plan {by_plan:?}, speedup {:.1}x",
+                     by_id.as_secs_f64() / by_plan.as_secs_f64());
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Synthetic datasets. Column names of a generator are the neuron ids of the matching built-in network
+//! where there is one (k, x, b -> y; a, b, c -> x1, x2).
+use crate::dataset::{Dataset, Sample};
+use crate::initializers::standard_normal;
+use crate::quadratic::{QuadraticGenerator, QuadraticOutput, RootsKind};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GeneratorFunction {
+    /// y = kx + b
+    Linear,
+    /// Roots or the number of real roots of ax^2 + bx + c = 0, see [`QuadraticGenerator`]
+    QuadraticRoots { output: QuadraticOutput, skip: Vec<RootsKind>, mask: Vec<RootsKind> },
+    /// y = c0 + c1 x + c2 x^2 + ...
+    Polynomial { coefficients: Vec<f32> },
+    Sin,
+    Cos,
+    /// Inputs are min or max of the range, y = 1 when they differ
+    Xor,
+    /// Points of two interleaved spirals, class 0 or 1
+    TwoSpirals { turns: f32 },
+    /// y = ab
+    Product,
+}
+
+impl FromStr for GeneratorFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "linear" => Ok(GeneratorFunction::Linear),
+            "quadratic" | "quadratic-roots" => Ok(GeneratorFunction::QuadraticRoots {
+                output: QuadraticOutput::Roots,
+                skip: vec![RootsKind::Complex, RootsKind::Degenerate],
+                mask: vec![],
+            }),
+            "quadratic-complex" => Ok(GeneratorFunction::QuadraticRoots {
+                output: QuadraticOutput::Complex,
+                skip: vec![RootsKind::Degenerate],
+                mask: vec![],
+            }),
+            "quadratic-class" => Ok(GeneratorFunction::QuadraticRoots {
+                output: QuadraticOutput::Class,
+                skip: vec![RootsKind::Degenerate],
+                mask: vec![],
+            }),
+            "polynomial" => Ok(GeneratorFunction::Polynomial { coefficients: vec![0.0, 0.0, 1.0] }),
+            "sin" => Ok(GeneratorFunction::Sin),
+            "cos" => Ok(GeneratorFunction::Cos),
+            "xor" => Ok(GeneratorFunction::Xor),
+            "spirals" | "two-spirals" => Ok(GeneratorFunction::TwoSpirals { turns: 1.5 }),
+            "product" => Ok(GeneratorFunction::Product),
+            _ => Err(format!("unknown generator: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorConfig {
+    pub function: GeneratorFunction,
+    pub count: usize,
+    /// Range of the drawn input values
+    pub min: f32,
+    pub max: f32,
+    /// Whole numbers only
+    #[serde(default)]
+    pub integer: bool,
+    /// Standard deviation of the gaussian noise, added to the inputs of classification datasets
+    /// (xor, spirals) and to the outputs of the others
+    #[serde(default)]
+    pub noise: f32,
+}
+
+/// 100 integer samples of y = kx + b in -10..10
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig { function: GeneratorFunction::Linear, count: 100, min: -10.0, max: 10.0, integer: true, noise: 0.0 }
+    }
+}
+
+/// Where the input values come from
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+    pub integer: bool,
+}
+
+impl Range {
+    pub fn draw(&self, rng: &mut dyn RngCore) -> f32 {
+        if self.integer {
+            rng.random_range(self.min.ceil() as i64..=self.max.floor() as i64) as f32
+        } else {
+            rng.random_range(self.min..=self.max)
+        }
+    }
+}
+
+pub trait Generator {
+    /// Formula or short description, saved in the network metadata
+    fn describe(&self) -> String;
+    fn inputs(&self) -> Vec<&'static str>;
+    fn outputs(&self) -> Vec<&'static str>;
+    /// `None` when the drawn values do not make a sample
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample>;
+    /// Outputs are class labels, noise goes to the inputs
+    fn classification(&self) -> bool {
+        false
+    }
+}
+
+pub fn build(function: &GeneratorFunction) -> Box<dyn Generator> {
+    match function {
+        GeneratorFunction::Linear => Box::new(Linear),
+        GeneratorFunction::QuadraticRoots { output, skip, mask } =>
+            Box::new(QuadraticGenerator { output: *output, skip: skip.clone(), mask: mask.clone() }),
+        GeneratorFunction::Polynomial { coefficients } => Box::new(Polynomial { coefficients: coefficients.clone() }),
+        GeneratorFunction::Sin => Box::new(Unary { name: "sin", function: f32::sin }),
+        GeneratorFunction::Cos => Box::new(Unary { name: "cos", function: f32::cos }),
+        GeneratorFunction::Xor => Box::new(Xor),
+        GeneratorFunction::TwoSpirals { turns } => Box::new(TwoSpirals { turns: *turns }),
+        GeneratorFunction::Product => Box::new(Product),
+    }
+}
+
+/// Gives up when the generator keeps rejecting the drawn values
+const MAX_ATTEMPTS_PER_SAMPLE: usize = 1000;
+
+pub fn generate(config: &GeneratorConfig, rng: &mut dyn RngCore) -> Result<Dataset, String> {
+    //NaN не проходит ни одно сравнение, поэтому условия записаны как "должно быть"
+    if !(config.min.is_finite() && config.max.is_finite() && (config.max - config.min).is_finite()) {
+        return Err(format!("range {}..{} has to be finite", config.min, config.max));
+    }
+    if config.min > config.max || (config.integer && config.min.ceil() > config.max.floor()) {
+        return Err(format!("empty range {}..{}", config.min, config.max));
+    }
+    if !(config.noise >= 0.0 && config.noise.is_finite()) {
+        return Err(format!("noise has to be a finite non-negative number, got {}", config.noise));
+    }
+    if config.count == 0 {
+        return Err("no samples to generate".to_string());
+    }
+    let generator = build(&config.function);
+    let range = Range { min: config.min, max: config.max, integer: config.integer };
+    let mut samples = Vec::with_capacity(config.count);
+    let mut attempts = 0;
+    while samples.len() < config.count {
+        attempts += 1;
+        if attempts > MAX_ATTEMPTS_PER_SAMPLE * config.count {
+            return Err(format!("{}: only {} of {} samples in {}..{}",
+                               generator.describe(), samples.len(), config.count, config.min, config.max));
+        }
+        let Some(mut sample) = generator.sample(&range, rng) else {
+            continue;
+        };
+        if config.noise > 0.0 {
+            let values = if generator.classification() { &mut sample.inputs } else { &mut sample.outputs };
+            for value in values.iter_mut() {
+                *value += config.noise * standard_normal(rng);
+            }
+        }
+        samples.push(sample);
+    }
+    let names = |names: Vec<&str>| names.into_iter().map(String::from).collect();
+    Ok(Dataset { inputs: names(generator.inputs()), outputs: names(generator.outputs()), samples })
+}
+
+pub struct Linear;
+
+impl Generator for Linear {
+    fn describe(&self) -> String {
+        "y = kx + b".to_string()
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["k", "x", "b"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        vec!["y"]
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let (k, x, b) = (range.draw(rng), range.draw(rng), range.draw(rng));
+        Some(Sample { inputs: vec![k, x, b], outputs: vec![k * x + b] })
+    }
+}
+
+pub struct Polynomial {
+    /// From the constant term up
+    pub coefficients: Vec<f32>,
+}
+
+impl Generator for Polynomial {
+    fn describe(&self) -> String {
+        let terms: Vec<String> = self.coefficients.iter().enumerate()
+            .map(|(power, c)| match power {
+                0 => format!("{c}"),
+                1 => format!("{c}x"),
+                _ => format!("{c}x^{power}"),
+            })
+            .collect();
+        format!("y = {}", terms.join(" + "))
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["x"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        vec!["y"]
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let x = range.draw(rng);
+        //схема Горнера
+        let y = self.coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c);
+        Some(Sample { inputs: vec![x], outputs: vec![y] })
+    }
+}
+
+/// y = f(x)
+pub struct Unary {
+    pub name: &'static str,
+    pub function: fn(f32) -> f32,
+}
+
+impl Generator for Unary {
+    fn describe(&self) -> String {
+        format!("y = {}(x)", self.name)
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["x"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        vec!["y"]
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let x = range.draw(rng);
+        Some(Sample { inputs: vec![x], outputs: vec![(self.function)(x)] })
+    }
+}
+
+pub struct Xor;
+
+impl Generator for Xor {
+    fn describe(&self) -> String {
+        "y = a xor b".to_string()
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["a", "b"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        vec!["y"]
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let (a, b): (bool, bool) = (rng.random(), rng.random());
+        let value = |bit: bool| if bit { range.max } else { range.min };
+        Some(Sample { inputs: vec![value(a), value(b)], outputs: vec![if a != b { 1.0 } else { 0.0 }] })
+    }
+    fn classification(&self) -> bool {
+        true
+    }
+}
+
+/// The radius grows from 0 to the largest absolute value of the range
+pub struct TwoSpirals {
+    pub turns: f32,
+}
+
+impl Generator for TwoSpirals {
+    fn describe(&self) -> String {
+        format!("two spirals, {} turns", self.turns)
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["x", "y"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        vec!["class"]
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let t: f32 = rng.random();
+        let class: bool = rng.random();
+        let radius = t * range.min.abs().max(range.max.abs());
+        //вторая спираль повёрнута на пол-оборота
+        let angle = t * self.turns * 2.0 * PI + if class { PI } else { 0.0 };
+        Some(Sample {
+            inputs: vec![radius * angle.cos(), radius * angle.sin()],
+            outputs: vec![if class { 1.0 } else { 0.0 }],
+        })
+    }
+    fn classification(&self) -> bool {
+        true
+    }
+}
+
+pub struct Product;
+
+impl Generator for Product {
+    fn describe(&self) -> String {
+        "y = ab".to_string()
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["a", "b"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        vec!["y"]
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let (a, b) = (range.draw(rng), range.draw(rng));
+        Some(Sample { inputs: vec![a, b], outputs: vec![a * b] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generators::{generate, GeneratorConfig, GeneratorFunction};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn config(function: &str) -> GeneratorConfig {
+        GeneratorConfig { function: function.parse().unwrap(), count: 200, min: -5.0, max: 5.0, integer: false, noise: 0.0 }
+    }
+
+    #[test]
+    fn every_generator() {
+        for name in ["linear", "quadratic", "quadratic-complex", "quadratic-class", "polynomial", "sin", "cos", "xor", "spirals", "product"] {
+            let dataset = generate(&config(name), &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+            assert_eq!(dataset.samples.len(), 200, "{name}");
+            for sample in dataset.samples.iter() {
+                assert_eq!(sample.inputs.len(), dataset.inputs.len(), "{name}");
+                assert_eq!(sample.outputs.len(), dataset.outputs.len(), "{name}");
+                assert!(sample.inputs.iter().chain(&sample.outputs).all(|v| v.is_finite()), "{name}: {sample:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn quadratic_roots_solve_the_equation() {
+        let dataset = generate(&config("quadratic"), &mut ChaCha8Rng::seed_from_u64(2)).unwrap();
+        for sample in dataset.samples.iter() {
+            let [a, b, c] = sample.inputs[..] else { panic!() };
+            for x in sample.outputs.iter() {
+                let residual = a * x * x + b * x + c;
+                assert!(residual.abs() < EPSILON * (1.0 + b.abs() * x.abs()), "{sample:?}: {residual}");
+            }
+        }
+    }
+
+    #[test]
+    fn seed_range_and_noise() {
+        let mut linear = GeneratorConfig::default();
+        let first = generate(&linear, &mut ChaCha8Rng::seed_from_u64(3)).unwrap();
+        let second = generate(&linear, &mut ChaCha8Rng::seed_from_u64(3)).unwrap();
+        assert_eq!(first.samples, second.samples);
+        assert!(first.samples.iter().flat_map(|s| &s.inputs).all(|v| v.fract() == 0.0 && (-10.0..=10.0).contains(v)));
+
+        linear.noise = 0.5;
+        let noisy = generate(&linear, &mut ChaCha8Rng::seed_from_u64(3)).unwrap();
+        assert!(noisy.samples.iter().any(|s| { let [k, x, b] = s.inputs[..] else { panic!() }; s.outputs[0] != k * x + b }));
+
+        let xor = GeneratorConfig { function: GeneratorFunction::Xor, min: 0.0, max: 1.0, ..GeneratorConfig::default() };
+        for sample in generate(&xor, &mut ChaCha8Rng::seed_from_u64(4)).unwrap().samples {
+            assert_eq!(sample.outputs[0], (sample.inputs[0] - sample.inputs[1]).abs());
+        }
+
+        let impossible = GeneratorConfig { function: "quadratic".parse().unwrap(), min: 1.0, max: 1.0, ..GeneratorConfig::default() };
+        assert!(generate(&impossible, &mut ChaCha8Rng::seed_from_u64(5)).is_err());
+    }
+
+    #[test]
+    fn invalid_configs_rejected() {
+        let error = |config: GeneratorConfig| generate(&config, &mut ChaCha8Rng::seed_from_u64(6)).unwrap_err();
+        let config = GeneratorConfig { integer: false, ..GeneratorConfig::default() };
+        assert_eq!(error(GeneratorConfig { min: f32::NAN, ..config.clone() }), "range NaN..10 has to be finite");
+        assert_eq!(error(GeneratorConfig { max: f32::INFINITY, ..config.clone() }), "range -10..inf has to be finite");
+        assert!(error(GeneratorConfig { min: -3e38, max: 3e38, ..config.clone() }).ends_with("has to be finite"));
+        assert_eq!(error(GeneratorConfig { min: 2.0, max: 1.0, ..config.clone() }), "empty range 2..1");
+        assert_eq!(error(GeneratorConfig { noise: -0.1, ..config.clone() }), "noise has to be a finite non-negative number, got -0.1");
+        assert_eq!(error(GeneratorConfig { count: 0, ..config }), "no samples to generate");
+    }
+}
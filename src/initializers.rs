@@ -0,0 +1,100 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// How the weights of a layer get their initial values.
+/// `fan_in` is the number of input links of a neuron, `fan_out` the number of neurons in the layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Initializer {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Normal { mean: f32, std_dev: f32 },
+    /// Glorot: U(-l, l), l = sqrt(6 / (fan_in + fan_out))
+    XavierUniform,
+    /// Glorot: N(0, 2 / (fan_in + fan_out))
+    XavierNormal,
+    /// Kaiming: U(-l, l), l = sqrt(6 / fan_in)
+    HeUniform,
+    /// Kaiming: N(0, 2 / fan_in)
+    HeNormal,
+}
+
+impl Initializer {
+    /// Parameters come from user-written topology files
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Initializer::Constant(value) if !value.is_finite() => Err(format!("constant {value} is not finite")),
+            Initializer::Uniform { min, max } if !(min.is_finite() && max.is_finite() && min < max) =>
+                Err(format!("uniform range {min}..{max} has to be finite with min < max")),
+            Initializer::Normal { mean, std_dev } if !(mean.is_finite() && std_dev.is_finite() && *std_dev >= 0.0) =>
+                Err(format!("normal mean {mean}, std_dev {std_dev} have to be finite, std_dev >= 0")),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn weight(&self, fan_in: usize, fan_out: usize, rng: &mut impl Rng) -> f32 {
+        let fan_in = fan_in.max(1) as f32;
+        let fan_out = fan_out.max(1) as f32;
+        match self {
+            Initializer::Constant(value) => *value,
+            Initializer::Uniform { min, max } => rng.random_range(*min..*max),
+            Initializer::Normal { mean, std_dev } => mean + std_dev * standard_normal(rng),
+            Initializer::XavierUniform => symmetric_uniform((6.0 / (fan_in + fan_out)).sqrt(), rng),
+            Initializer::XavierNormal => (2.0 / (fan_in + fan_out)).sqrt() * standard_normal(rng),
+            Initializer::HeUniform => symmetric_uniform((6.0 / fan_in).sqrt(), rng),
+            Initializer::HeNormal => (2.0 / fan_in).sqrt() * standard_normal(rng),
+        }
+    }
+}
+
+fn symmetric_uniform(limit: f32, rng: &mut impl Rng) -> f32 {
+    rng.random_range(-limit..limit)
+}
+
+/// Box-Muller transform
+pub fn standard_normal(rng: &mut (impl Rng + ?Sized)) -> f32 {
+    let u1: f32 = 1.0 - rng.random::<f32>(); // (0, 1], ln(0) is not allowed
+    let u2: f32 = rng.random();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::initializers::Initializer;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn sample(initializer: &Initializer, fan_in: usize, fan_out: usize) -> Vec<f32> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..20000).map(|_| initializer.weight(fan_in, fan_out, &mut rng)).collect()
+    }
+
+    fn std_dev(values: &[f32]) -> f32 {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn spread_matches_fan() {
+        let he = sample(&Initializer::HeNormal, 32, 16);
+        assert!((std_dev(&he) - (2.0f32 / 32.0).sqrt()).abs() < 0.01);
+
+        let xavier = sample(&Initializer::XavierNormal, 32, 16);
+        assert!((std_dev(&xavier) - (2.0f32 / 48.0).sqrt()).abs() < 0.01);
+
+        let limit = (6.0f32 / 48.0).sqrt();
+        assert!(sample(&Initializer::XavierUniform, 32, 16).iter().all(|w| w.abs() <= limit));
+        let limit = (6.0f32 / 32.0).sqrt();
+        assert!(sample(&Initializer::HeUniform, 32, 16).iter().all(|w| w.abs() <= limit));
+
+        let normal = sample(&Initializer::Normal { mean: 1.0, std_dev: 0.5 }, 1, 1);
+        let mean = normal.iter().sum::<f32>() / normal.len() as f32;
+        assert!((mean - 1.0).abs() < 0.02);
+        assert!((std_dev(&normal) - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn same_seed_same_weights() {
+        assert_eq!(sample(&Initializer::HeUniform, 8, 8), sample(&Initializer::HeUniform, 8, 8));
+    }
+}
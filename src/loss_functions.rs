@@ -0,0 +1,144 @@
+use crate::nn_objects::LossFunction;
+
+/// Loss of a single output neuron.
+/// `gradient` follows the sign convention of `Neuron::error`: it is `-dL/d(output)`,
+/// positive when the output has to grow.
+pub trait Loss {
+    fn value(&self, target: f32, output: f32) -> f32;
+    fn gradient(&self, target: f32, output: f32) -> f32;
+}
+
+pub fn build(function: &LossFunction) -> Box<dyn Loss> {
+    match function {
+        LossFunction::Mse => Box::new(Mse),
+        LossFunction::Mae => Box::new(Mae),
+        LossFunction::Huber { delta } => Box::new(Huber { delta: *delta }),
+        LossFunction::LogCosh => Box::new(LogCosh),
+        LossFunction::Relative => Box::new(Relative),
+    }
+}
+
+/// (t - o)^2
+pub struct Mse;
+
+impl Loss for Mse {
+    fn value(&self, target: f32, output: f32) -> f32 {
+        (target - output).powi(2)
+    }
+    fn gradient(&self, target: f32, output: f32) -> f32 {
+        2.0 * (target - output)
+    }
+}
+
+/// |t - o|
+pub struct Mae;
+
+impl Loss for Mae {
+    fn value(&self, target: f32, output: f32) -> f32 {
+        (target - output).abs()
+    }
+    fn gradient(&self, target: f32, output: f32) -> f32 {
+        if target == output { 0.0 } else { (target - output).signum() }
+    }
+}
+
+/// Quadratic near zero, linear outside of `delta`
+pub struct Huber {
+    pub delta: f32,
+}
+
+impl Loss for Huber {
+    fn value(&self, target: f32, output: f32) -> f32 {
+        let r = (target - output).abs();
+        if r <= self.delta {
+            0.5 * r * r
+        } else {
+            self.delta * (r - 0.5 * self.delta)
+        }
+    }
+    fn gradient(&self, target: f32, output: f32) -> f32 {
+        let r = target - output;
+        r.clamp(-self.delta, self.delta)
+    }
+}
+
+/// ln(cosh(t - o))
+pub struct LogCosh;
+
+impl Loss for LogCosh {
+    fn value(&self, target: f32, output: f32) -> f32 {
+        // ln(cosh(r)) = |r| + ln(1 + e^(-2|r|)) - ln(2), does not overflow for large r
+        let r = (target - output).abs();
+        r + (-2.0 * r).exp().ln_1p() - std::f32::consts::LN_2
+    }
+    fn gradient(&self, target: f32, output: f32) -> f32 {
+        (target - output).tanh()
+    }
+}
+
+/// ||t| - |o|| / max(|t|, |o|), zero when both are zero. Compares magnitudes only, so t = 2, o = -2 is no loss.
+/// `gradient` is the legacy heuristic: the signed relative error, not the derivative of `value`
+pub struct Relative;
+
+impl Loss for Relative {
+    fn value(&self, target: f32, output: f32) -> f32 {
+        self.gradient(target, output).abs()
+    }
+    fn gradient(&self, target: f32, output: f32) -> f32 {
+        let sign = (target - output).signum();
+        let t = target.abs();
+        let o = output.abs();
+        let max = t.max(o);
+        if max == 0.0 {
+            return 0.0;
+        }
+        let diff = (t - o).abs();
+        diff / max * sign
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::loss_functions::build;
+    use crate::nn_objects::LossFunction;
+
+    const EPSILON: f32 = 1e-3;
+
+    #[test]
+    fn gradient_matches_value() {
+        for function in [
+            LossFunction::Mse,
+            LossFunction::Mae,
+            LossFunction::Huber { delta: 1.0 },
+            LossFunction::LogCosh,
+        ] {
+            let loss = build(&function);
+            for (target, output) in [(1.0, 0.2), (-2.0, 1.5), (0.3, 0.5), (4.0, -3.0)] {
+                let numeric = -(loss.value(target, output + EPSILON) - loss.value(target, output - EPSILON)) / (2.0 * EPSILON);
+                let analytic = loss.gradient(target, output);
+                assert!((analytic - numeric).abs() < 1e-2,
+                        "{:?} t={} o={}: analytic {}, numeric {}", function, target, output, analytic, numeric);
+            }
+        }
+    }
+
+    #[test]
+    fn relative_loss_zero_target() {
+        let loss = build(&LossFunction::Relative);
+        assert_eq!(loss.value(0.0, 0.0), 0.0);
+        assert_eq!(loss.gradient(0.0, 0.0), 0.0);
+        assert_eq!(loss.gradient(0.0, 2.0), -1.0);
+        assert_eq!(loss.gradient(4.0, 2.0), 0.5);
+    }
+
+    #[test]
+    fn relative_loss_compares_magnitudes() {
+        let loss = build(&LossFunction::Relative);
+        assert_eq!(loss.value(2.0, -2.0), 0.0);
+        assert_eq!(loss.gradient(2.0, -2.0), 0.0);
+        //знак разности t - o, величина - относительная ошибка
+        assert_eq!(loss.gradient(-4.0, 2.0), -0.5);
+        assert_eq!(loss.gradient(2.0, -4.0), 0.5);
+        assert_eq!(loss.value(2.0, -4.0), 0.5);
+    }
+}
@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+/// Learning rate policy and its parameters.
+/// Steps are train steps (weight updates), epochs are passes over the train set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LrScheduleFunction {
+    Constant,
+    /// Multiply by `gamma` every `step_epochs` epochs
+    Step { step_epochs: u64, gamma: f32 },
+    /// Multiply by `gamma` every step, not lower than `min_rate`
+    Exponential { gamma: f32, min_rate: f32 },
+    /// Cosine from the base rate down to `min_rate` and back every `period` steps
+    Cosine { period: u64, min_rate: f32 },
+    /// Linear growth from zero during `warmup_steps`, then exponential decay
+    Warmup { warmup_steps: u64, gamma: f32, min_rate: f32 },
+    /// Multiply by `factor` when the epoch error has not improved by `threshold` (relative)
+    /// for more than `patience` epochs
+    ReduceOnPlateau { factor: f32, patience: u64, threshold: f32, min_rate: f32 },
+}
+
+impl Default for LrScheduleFunction {
+    fn default() -> Self {
+        LrScheduleFunction::Exponential { gamma: 0.999, min_rate: 0.001 }
+    }
+}
+
+impl FromStr for LrScheduleFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "constant" => Ok(LrScheduleFunction::Constant),
+            "step" => Ok(LrScheduleFunction::Step { step_epochs: 10, gamma: 0.5 }),
+            "exponential" => Ok(LrScheduleFunction::default()),
+            "cosine" => Ok(LrScheduleFunction::Cosine { period: 1000, min_rate: 0.0001 }),
+            "warmup" => Ok(LrScheduleFunction::Warmup { warmup_steps: 100, gamma: 0.999, min_rate: 0.001 }),
+            "plateau" => Ok(LrScheduleFunction::ReduceOnPlateau { factor: 0.5, patience: 5, threshold: 0.01, min_rate: 0.0001 }),
+            _ => Err(format!("unknown learning rate schedule: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LrScheduleState {
+    pub function: LrScheduleFunction,
+    pub base_rate: f32,
+    pub rate: f32,
+    pub step: u64,
+    pub epoch: u64,
+    pub best_error: f32,
+    pub bad_epochs: u64,
+}
+
+impl LrScheduleState {
+    pub fn new(function: LrScheduleFunction, base_rate: f32) -> Self {
+        LrScheduleState {
+            function,
+            base_rate,
+            rate: base_rate,
+            step: 0,
+            epoch: 0,
+            //не INFINITY: json его не сохраняет
+            best_error: f32::MAX,
+            bad_epochs: 0,
+        }
+    }
+}
+
+pub trait LrSchedule {
+    fn learning_rate(&self) -> f32;
+    /// Called after every weight update
+    fn on_step(&mut self);
+    /// Called after every epoch with the mean error of the epoch
+    fn on_epoch_end(&mut self, epoch_error: f32);
+    fn state(&self) -> LrScheduleState;
+}
+
+pub fn build(state: LrScheduleState) -> Box<dyn LrSchedule> {
+    match state.function.clone() {
+        LrScheduleFunction::Constant => Box::new(Constant { state }),
+        LrScheduleFunction::Step { step_epochs, gamma } => Box::new(StepDecay { step_epochs, gamma, state }),
+        LrScheduleFunction::Exponential { gamma, min_rate } => Box::new(Exponential { gamma, min_rate, state }),
+        LrScheduleFunction::Cosine { period, min_rate } => Box::new(Cosine { period, min_rate, state }),
+        LrScheduleFunction::Warmup { warmup_steps, gamma, min_rate } => Box::new(Warmup { warmup_steps, gamma, min_rate, state }),
+        LrScheduleFunction::ReduceOnPlateau { factor, patience, threshold, min_rate } =>
+            Box::new(ReduceOnPlateau { factor, patience, threshold, min_rate, state }),
+    }
+}
+
+pub struct Constant {
+    state: LrScheduleState,
+}
+
+impl LrSchedule for Constant {
+    fn learning_rate(&self) -> f32 {
+        self.state.rate
+    }
+    fn on_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn on_epoch_end(&mut self, _epoch_error: f32) {
+        self.state.epoch += 1;
+    }
+    fn state(&self) -> LrScheduleState {
+        self.state.clone()
+    }
+}
+
+pub struct StepDecay {
+    step_epochs: u64,
+    gamma: f32,
+    state: LrScheduleState,
+}
+
+impl LrSchedule for StepDecay {
+    fn learning_rate(&self) -> f32 {
+        self.state.rate
+    }
+    fn on_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn on_epoch_end(&mut self, _epoch_error: f32) {
+        self.state.epoch += 1;
+        let decays = (self.state.epoch / self.step_epochs.max(1)) as i32;
+        self.state.rate = self.state.base_rate * self.gamma.powi(decays);
+    }
+    fn state(&self) -> LrScheduleState {
+        self.state.clone()
+    }
+}
+
+pub struct Exponential {
+    gamma: f32,
+    min_rate: f32,
+    state: LrScheduleState,
+}
+
+impl LrSchedule for Exponential {
+    fn learning_rate(&self) -> f32 {
+        self.state.rate
+    }
+    fn on_step(&mut self) {
+        self.state.step += 1;
+        if self.state.rate > self.min_rate {
+            self.state.rate *= self.gamma;
+        }
+    }
+    fn on_epoch_end(&mut self, _epoch_error: f32) {
+        self.state.epoch += 1;
+    }
+    fn state(&self) -> LrScheduleState {
+        self.state.clone()
+    }
+}
+
+pub struct Cosine {
+    period: u64,
+    min_rate: f32,
+    state: LrScheduleState,
+}
+
+impl LrSchedule for Cosine {
+    fn learning_rate(&self) -> f32 {
+        self.state.rate
+    }
+    fn on_step(&mut self) {
+        self.state.step += 1;
+        let period = self.period.max(1);
+        let phase = (self.state.step % period) as f32 / period as f32;
+        self.state.rate = self.min_rate + (self.state.base_rate - self.min_rate) * (1.0 + (PI * phase).cos()) / 2.0;
+    }
+    fn on_epoch_end(&mut self, _epoch_error: f32) {
+        self.state.epoch += 1;
+    }
+    fn state(&self) -> LrScheduleState {
+        self.state.clone()
+    }
+}
+
+pub struct Warmup {
+    warmup_steps: u64,
+    gamma: f32,
+    min_rate: f32,
+    state: LrScheduleState,
+}
+
+impl LrSchedule for Warmup {
+    fn learning_rate(&self) -> f32 {
+        if self.state.step < self.warmup_steps {
+            self.state.base_rate * (self.state.step + 1) as f32 / self.warmup_steps as f32
+        } else {
+            self.state.rate
+        }
+    }
+    fn on_step(&mut self) {
+        self.state.step += 1;
+        if self.state.step > self.warmup_steps && self.state.rate > self.min_rate {
+            self.state.rate *= self.gamma;
+        }
+    }
+    fn on_epoch_end(&mut self, _epoch_error: f32) {
+        self.state.epoch += 1;
+    }
+    fn state(&self) -> LrScheduleState {
+        self.state.clone()
+    }
+}
+
+pub struct ReduceOnPlateau {
+    factor: f32,
+    patience: u64,
+    threshold: f32,
+    min_rate: f32,
+    state: LrScheduleState,
+}
+
+impl LrSchedule for ReduceOnPlateau {
+    fn learning_rate(&self) -> f32 {
+        self.state.rate
+    }
+    fn on_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn on_epoch_end(&mut self, epoch_error: f32) {
+        self.state.epoch += 1;
+        if epoch_error < self.state.best_error * (1.0 - self.threshold) {
+            self.state.best_error = epoch_error;
+            self.state.bad_epochs = 0;
+        } else {
+            self.state.bad_epochs += 1;
+            if self.state.bad_epochs > self.patience {
+                self.state.rate = (self.state.rate * self.factor).max(self.min_rate);
+                self.state.bad_epochs = 0;
+            }
+        }
+    }
+    fn state(&self) -> LrScheduleState {
+        self.state.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lr_schedules::{build, LrScheduleFunction, LrScheduleState};
+
+    const EPSILON: f32 = 1e-6;
+
+    #[test]
+    fn step_decay() {
+        let function = LrScheduleFunction::Step { step_epochs: 2, gamma: 0.5 };
+        let mut schedule = build(LrScheduleState::new(function, 0.1));
+        let mut rates = vec![];
+        for _ in 0..5 {
+            schedule.on_epoch_end(1.0);
+            rates.push(schedule.learning_rate());
+        }
+        assert_eq!(rates, vec![0.1, 0.05, 0.05, 0.025, 0.025]);
+    }
+
+    #[test]
+    fn cosine_annealing() {
+        let function = LrScheduleFunction::Cosine { period: 4, min_rate: 0.0 };
+        let mut schedule = build(LrScheduleState::new(function, 1.0));
+        schedule.on_step();
+        schedule.on_step();
+        assert!((schedule.learning_rate() - 0.5).abs() < EPSILON);
+        schedule.on_step();
+        schedule.on_step();
+        assert!((schedule.learning_rate() - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn warmup_then_decay() {
+        let function = LrScheduleFunction::Warmup { warmup_steps: 4, gamma: 0.5, min_rate: 0.0 };
+        let mut schedule = build(LrScheduleState::new(function, 1.0));
+        let mut rates = vec![];
+        for _ in 0..6 {
+            rates.push(schedule.learning_rate());
+            schedule.on_step();
+        }
+        assert_eq!(rates, vec![0.25, 0.5, 0.75, 1.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn reduce_on_plateau() {
+        let function = LrScheduleFunction::ReduceOnPlateau { factor: 0.1, patience: 1, threshold: 0.0, min_rate: 0.0 };
+        let mut schedule = build(LrScheduleState::new(function, 1.0));
+        schedule.on_epoch_end(1.0);
+        schedule.on_epoch_end(0.5);
+        schedule.on_epoch_end(0.5);
+        assert_eq!(schedule.learning_rate(), 1.0);
+        schedule.on_epoch_end(0.6);
+        assert!((schedule.learning_rate() - 0.1).abs() < EPSILON);
+    }
+}
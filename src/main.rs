@@ -1,354 +1,427 @@
+
 mod activation_functions;
+mod backpropagation;
+mod checkpoint;
+mod cli;
+mod dataset;
+mod dense;
 mod draw;
 mod draw_adapter;
+mod initializers;
+mod execution_context;
 mod execution_objects;
+mod execution_plan;
+mod generators;
+mod loss_functions;
+mod lr_schedules;
+mod nan_guard;
+mod model_binary;
+mod model_file;
 mod nn_build;
 mod nn_objects;
+mod normalization;
+mod optimizers;
+mod progress_log;
+mod quadratic;
+mod topology;
 mod train_data;
+mod validation;
 
-use crate::activation_functions::{apply, derivative};
+use crate::checkpoint::Checkpoint;
+use crate::dense::DenseNetwork;
+use crate::cli::{Backend, BatchSize, Builtin, Cli, Command, ConvertArgs, DatasetArgs, EvalArgs, GenerateArgs, GeneratorArgs, InspectArgs, NetworkArgs, PredictArgs, TrainArgs};
 use crate::draw::macroquad_draw::spawn_ui_thread;
 use crate::draw::objects::Model;
 use crate::draw::view::build_view;
 use crate::draw_adapter::DrawAdapter;
-use crate::execution_objects::{Events, ExecutionObjects, RunMode};
-use crate::nn_build::build_nn1;
+use crate::execution_context::{evaluate, set_inputs, ExecutionContext};
+use crate::execution_objects::{Events, RunMode, TrainProgress};
+use crate::lr_schedules::{LrScheduleFunction, LrScheduleState};
+use crate::nn_build::{build_nn, build_nn1};
 use crate::nn_objects::Network;
-use crate::train_data::{load_kx_b, TrainItemCommon};
-use rand::prelude::ThreadRng;
-use rand::Rng;
+use crate::normalization::{Normalization, Scaling};
+use crate::optimizers::OptimizerState;
+use crate::progress_log::ProgressLog;
+use crate::topology::TopologySpec;
+use crate::dataset::{Dataset, Sample};
+use crate::generators::GeneratorConfig;
+use crate::train_data::split;
+use clap::Parser;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
-use std::sync::mpsc::Receiver;
-use std::time::{Duration, Instant};
-use serde_json::json;
-
-const STEPPING_DURATION: Duration = Duration::from_millis(1000);
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let path = Path::new("nn.json");
-    let mut nn = if path.exists() {
-        let nn_json = fs::read_to_string(path)?;
-        serde_json::from_str(&nn_json)?
-    } else {
-       build_nn1()
+    match Cli::parse().command {
+        Command::Train(args) => train(*args),
+        Command::Eval(args) => eval(args),
+        Command::Predict(args) => predict(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Convert(args) => convert(args),
+        Command::Generate(args) => generate(args),
+    }
+}
+
+fn train(args: TrainArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.backend == Backend::Dense && !args.headless {
+        return Err("--backend dense has no UI, use it with --headless".into());
+    }
+    if let Some(factor) = args.rollback
+        && !(factor > 0.0 && factor < 1.0) {
+        return Err(format!("--rollback has to be between 0 and 1, got {factor}").into());
+    }
+    //при продолжении данные воссоздаются из того же зерна, поэтому сеть строится так же, как в первый раз
+    let checkpoint = args.resume.as_deref().map(Checkpoint::load).transpose()?;
+    let seed = match &checkpoint {
+        Some(checkpoint) => checkpoint.network.metadata.seed.ok_or("checkpoint has no seed")?,
+        None => resolve_seed(args.seed),
+    };
+    //один генератор на всё: веса, данные, перемешивание, dropout
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut nn = load_network(&args.network, &mut rng)?;
+    if let Some(checkpoint) = &checkpoint {
+        nn = checkpoint.network.clone();
+    }
+    nn.metadata.seed = Some(seed);
+    nn.metadata.dataset = Some(match &args.dataset.dataset {
+        Some(path) => path.display().to_string(),
+        None => {
+            let generator = generators::build(&generator_config(&args.dataset.generator)?.function);
+            format!("generated {}", generator.describe())
+        }
+    });
+    if let Some(loss) = args.loss.clone() {
+        nn.metadata.loss = loss;
+    }
+    let loss = loss_functions::build(&nn.metadata.loss);
+
+    //состояние оптимизатора сохраняется рядом с сетью, чтобы продолжить обучение
+    let saved_state: Option<OptimizerState> = match args.network.network.as_deref().map(optimizer_state_path) {
+        Some(path) if path.exists() => Some(serde_json::from_str(&fs::read_to_string(path)?)?),
+        _ => None,
+    };
+    let optimizer_state = match (args.optimizer.clone(), saved_state) {
+        (Some(function), Some(state)) if function == state.function => state,
+        (Some(function), _) => OptimizerState::new(function),
+        (None, Some(state)) => state,
+        (None, None) => OptimizerState::default(),
     };
-    
+    let parameters_count = nn.parameters_count();
+    for moment in [&optimizer_state.first_moment, &optimizer_state.second_moment] {
+        if !moment.is_empty() && moment.len() != parameters_count {
+            return Err(format!("optimizer state has {} parameters, network {parameters_count}", moment.len()).into());
+        }
+    }
+    let optimizer = optimizers::build(optimizer_state);
 
-    let (tx_data, rx_data) = mpsc::channel::<Model>();
+    let schedule_function: LrScheduleFunction = match &args.lr_schedule_file {
+        Some(file) => serde_json::from_str(&fs::read_to_string(file)?)?,
+        None => args.lr_schedule.clone().unwrap_or_default(),
+    };
+    let schedule = lr_schedules::build(LrScheduleState::new(schedule_function, args.learning_rate));
+
+    //без окна: сразу Running, кадры отбрасываются, прогресс в stdout/лог
+    let mut log = ProgressLog::new(args.log.as_deref())?;
+    let log_every = args.log_every.max(1);
+    log.line(&format!("seed {seed}"));
     let (tx_events, rx_events) = mpsc::channel::<Events>();
-    let view = build_view(&nn);
-    let join_handle = spawn_ui_thread(view, rx_data, tx_events);
-    let mut adapter = DrawAdapter::new(tx_data);
-
-    let mut learning_rate = 0.01;
-    let mut rng = rand::rng();
-    let mut iteration = 0;
-    let mut last_step = Instant::now();
-    let mut run_mode = RunMode::Pause;
-
-    let train_items = load_kx_b();
-    
+    let (adapter, join_handle, run_mode) = if args.headless {
+        drop(tx_events);
+        (DrawAdapter::headless(), None, RunMode::Running)
+    } else {
+        let (tx_data, rx_data) = mpsc::channel::<Model>();
+        let view = build_view(&nn);
+        let join_handle = spawn_ui_thread(view, rx_data, tx_events);
+        (DrawAdapter::new(tx_data, args.show_raw), Some(join_handle), RunMode::Pause)
+    };
+
+    let data = split(load_items(&args.dataset, &nn, &mut rng)?, args.validation_split, args.test_split, &mut rng)?;
+    let train_items = data.train;
+    //масштаб подбирается по обучающей части, при продолжении берётся из контрольной точки
+    if checkpoint.is_none() && (args.normalize_inputs.is_some() || args.normalize_outputs.is_some()) {
+        let inputs = args.normalize_inputs.unwrap_or(Scaling::None);
+        let outputs = args.normalize_outputs.unwrap_or(Scaling::None);
+        nn.normalization = Some(Normalization::fit(&train_items, inputs, outputs));
+        log.line(&format!("normalization: inputs {inputs:?}, outputs {outputs:?}"));
+    }
+    let batch_size = match args.batch_size {
+        BatchSize::Samples(size) => size,
+        BatchSize::Full => train_items.len(),
+    };
+
     let mut execution = ExecutionContext {
         nn,
-        iteration,
+        iteration: 0,
         error: 1.0,
-        last_step,
-        learning_rate,
+        batch_size,
+        batch_samples: 0,
+        gradients: vec![],
+        schedule,
+        loss,
+        optimizer,
         rng,
+        sample_start_rng: None,
+        progress: TrainProgress::default(),
+        checkpoint_path: Some(args.checkpoint.clone().unwrap_or_else(|| checkpoint_path(&args.output))),
+        dense: None,
+        rollback_factor: args.rollback,
+        rollbacks: 0,
+        divergence: None,
         run_mode,
         tx_adapter: adapter,
         rx_events,
     };
-    
+
+    if let Some(checkpoint) = checkpoint {
+        execution.restore(checkpoint);
+        log.line(&format!("resumed at epoch {}, sample {}", execution.progress.epoch + 1, execution.progress.sample));
+    }
+
+    if args.backend == Backend::Dense {
+        execution.dense = Some(DenseNetwork::from_network(&execution.nn)?);
+    }
+
+    if execution.rollback_factor.is_some() {
+        //откатываться нужно куда-то уже с первой эпохи
+        execution.save_checkpoint()?;
+    }
+
+    let checkpoint_every = args.checkpoint_every;
     loop {
-        let mut epoch_error =  0.0;
-        for train_item in train_items.iter() {
-            execution.train_loop(&train_item).expect("correct train loop");
-            iteration += 1;
-            epoch_error += execution.error.abs();          
+        if args.epochs.is_some_and(|epochs| execution.progress.epoch >= epochs) {
+            //контрольная точка, в которой все эпохи уже пройдены: сохраняем без обучения
+            log.line(&format!("already trained for {} epochs", execution.progress.epoch));
+            let final_loss = execution.progress.history.last().map(|r| r.validation_error);
+            finish_training(&mut execution, &args.output, final_loss, &data.test, &mut log)?;
+            break;
+        }
+        let epoch = execution.progress.epoch + 1;
+        let Some(epoch_error) = execution.train_epoch(&train_items)? else {
+            if let Some(divergence) = execution.divergence.take() {
+                log.line(&format!("diverged: {divergence}; rolled back to epoch {}, sample {}, learning rate {}",
+                                  execution.progress.epoch + 1, execution.progress.sample, execution.schedule.learning_rate()));
+            }
+            continue;
+        };
+        //сходимость определяем по отложенной выборке, если она есть
+        let validation_error = if data.validation.is_empty() {
+            epoch_error
+        } else {
+            execution.evaluate(&data.validation)?.loss
+        };
+        let last_epoch = args.epochs.is_some_and(|epochs| epoch >= epochs);
+        if epoch.is_multiple_of(log_every) || last_epoch {
+            log.line(&format!("epoch {epoch}, iteration {}: train error {epoch_error}, validation error {validation_error}, learning rate {}",
+                              execution.iteration, execution.schedule.learning_rate()));
         }
-        if epoch_error/(train_items.len() as f32) < 0.001 {
-            let json = serde_json::to_string_pretty(&execution.nn).unwrap();
-            fs::write("nn.json", json).unwrap();
-            execution.send_state_immidiately();
+        execution.end_epoch(epoch_error, validation_error);
+        if checkpoint_every > 0 && epoch.is_multiple_of(checkpoint_every) {
+            execution.save_checkpoint()?;
+        }
+        if validation_error < args.target_error || last_epoch {
+            finish_training(&mut execution, &args.output, Some(validation_error), &data.test, &mut log)?;
             break;
         }
     }
 
-    join_handle.join().unwrap();
+    if let Some(join_handle) = join_handle {
+        join_handle.join().unwrap();
+    }
     Ok(())
 }
 
-struct ExecutionContext {
-    nn: Network,
-    iteration: usize,
-    error: f32,
-    last_step: Instant,
-    learning_rate: f32,
-    rng: ThreadRng,
-    run_mode: RunMode,
-    tx_adapter: DrawAdapter,
-    rx_events: Receiver<Events>,
+/// Saves the network with its optimizer state and reports the test metrics
+fn finish_training(execution: &mut ExecutionContext, output: &Path, final_loss: Option<f32>, test: &[Sample],
+                   log: &mut ProgressLog) -> Result<(), Box<dyn std::error::Error>> {
+    execution.nn.metadata.final_loss = final_loss;
+    model_file::save(&execution.nn, output)?;
+    let json = serde_json::to_string_pretty(&execution.optimizer.state())?;
+    fs::write(optimizer_state_path(output), json)?;
+    execution.send_state_immidiately();
+    if !test.is_empty() {
+        let metrics = execution.evaluate(test)?;
+        log.line(&format!("test ({} samples): loss {}, mean absolute error {}, max absolute error {}",
+                          test.len(), metrics.loss, metrics.mean_absolute_error, metrics.max_absolute_error));
+    }
+    Ok(())
 }
 
-impl ExecutionContext {
-    pub fn train_loop(&mut self, train_item: &TrainItemCommon) -> Result<(), Box<dyn std::error::Error>> {
-        self.nn.layers[0].neurons[0].output = train_item.input_1;
-        self.nn.layers[0].neurons[1].output = train_item.input_2;
-        self.nn.layers[0].neurons[2].output = train_item.input_3;
-        self.forward();
-        self.send_state();
-        self.hang_out();
-        
-        let y_neuron = &mut self.nn.layers[self.nn.layers_count - 1].neurons[0];
-        let error = Self::loss(train_item.output_1, y_neuron.output);
-        
-        self.error = error;
-        
-        y_neuron.error = error;
-        self.backward();
-        self.send_state();
-        self.hang_out();
-
-        self.iteration += 1;        
-        if self.learning_rate > 0.001 {
-            self.learning_rate *= 0.999;
-        }
-        Ok(())
+fn eval(args: EvalArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(resolve_seed(args.seed));
+    let mut nn = load_network(&args.network, &mut rng)?;
+    let loss = loss_functions::build(args.loss.as_ref().unwrap_or(&nn.metadata.loss));
+    let items = load_items(&args.dataset, &nn, &mut rng)?;
+    let metrics = evaluate(&mut nn, loss.as_ref(), &items)?;
+    println!("{} samples: loss {}, mean absolute error {}, max absolute error {}",
+             items.len(), metrics.loss, metrics.mean_absolute_error, metrics.max_absolute_error);
+    Ok(())
+}
+
+fn predict(args: PredictArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut nn = load_network(&args.network, &mut ChaCha8Rng::seed_from_u64(resolve_seed(None)))?;
+    set_inputs(&mut nn, &args.inputs)?;
+    backpropagation::forward(&mut nn)?;
+    for (neuron, prediction) in nn.last().neurons.iter().zip(nn.predictions()) {
+        println!("{} = {prediction}", neuron.id);
     }
+    Ok(())
+}
 
-    fn forward(&mut self) {
-        for layer_index in 1..self.nn.layers_count {
-            let (prev, current) = self.nn.layers.split_at_mut(layer_index);
-            let prev_layer = &prev[layer_index - 1];
-            let current_layer = &mut current[0];
-
-            for neuron in &mut current_layer.neurons.iter_mut().filter(|n| !n.is_dummy()) {
-                let sum: f32 = neuron
-                    .input_links
-                    .iter()
-                    .filter(|link| !link.is_dummy())
-                    .map(|link| {
-                        return link.weight * prev_layer.get_value(&link.source_id);
-                    })
-                    .fold(0.0, |acc, e| acc + e);
-                neuron.sum_input = sum;
-                neuron.output = apply(&neuron.function_name, sum);
+fn inspect(args: InspectArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let nn = load_network(&args.network, &mut ChaCha8Rng::seed_from_u64(resolve_seed(None)))?;
+    println!("layers: {}, parameters: {}, loss: {:?}", nn.layers_count, nn.parameters_count(), nn.metadata.loss);
+    if let Some(normalization) = &nn.normalization {
+        println!("normalization: inputs {:?}, outputs {:?}", normalization.inputs, normalization.outputs);
+    }
+    for (index, layer) in nn.layers.iter().enumerate() {
+        println!("layer {index}:");
+        for neuron in layer.neurons.iter() {
+            let links: Vec<String> = neuron.input_links.iter()
+                .map(|l| format!("{}*{}", l.source_id, l.weight))
+                .collect();
+            if links.is_empty() {
+                println!("  {} {:?}", neuron.id, neuron.function_name);
+            } else {
+                println!("  {} {:?} bias {} <- {}", neuron.id, neuron.function_name, neuron.bias, links.join(", "));
             }
         }
     }
+    Ok(())
+}
 
-    fn backward(&mut self) {
-        for layer_index in (1..self.nn.layers_count).rev() {
-            let (prev, current) = self.nn.layers.split_at_mut(layer_index);
-            let prev_layer = &mut prev[layer_index - 1];
-            let current_layer = &mut current[0];
-
-            //распространяем ошибку
-            for prev_neuron in &mut prev_layer.neurons.iter_mut().filter(|n| !n.is_dummy()) {
-                //суммируем все ошибки, которые внес нейрон(ы) предыдущего слоя
-                let mut error_sum = 0.0;
-                for neuron in current_layer.neurons.iter().filter(|n| !n.is_dummy()) {
-                    //если есть связь между prev_neuron и нейроном текущего слоя
-                    for link in neuron
-                        .input_links
-                        .iter()
-                        .filter(|l| l.source_id == prev_neuron.id)
-                    {
-                        error_sum += link.weight * neuron.error;
-                    }
-                }
-                prev_neuron.error = error_sum;
-            }
-        }
+fn generate(args: GenerateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let seed = resolve_seed(args.seed);
+    let config = generator_config(&args.generator)?;
+    let dataset = generators::generate(&config, &mut ChaCha8Rng::seed_from_u64(seed))?;
+    dataset.save(&args.output)?;
+    println!("{}: {} samples, seed {seed} -> {}",
+             generators::build(&config.function).describe(), dataset.samples.len(), args.output.display());
+    Ok(())
+}
 
-        //обновляем веса
-        for layer_index in 1..self.nn.layers_count {
-            let (prev, current) = self.nn.layers.split_at_mut(layer_index);
-            let prev_layer = &prev[layer_index - 1];
-            let current_layer = &mut current[0];
-
-            for neuron in &mut current_layer.neurons.iter_mut().filter(|n| !n.is_dummy()) {
-                for link in neuron.input_links.iter_mut().filter(|l| !l.is_dummy()) {
-                    let derive = derivative(&neuron.function_name, neuron.sum_input);
-                    let mut delta = neuron.error * derive * prev_layer.get_value(&link.source_id);
-                    delta *= self.learning_rate;
-                    link.weight += delta;
-                }
-            }
-        }
-    }
+fn convert(args: ConvertArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let nn = load_network(&args.network, &mut ChaCha8Rng::seed_from_u64(resolve_seed(None)))?;
+    model_file::save(&nn, &args.output)?;
+    Ok(())
+}
 
-    fn send_state(&mut self) {
-        let execution_objects = ExecutionObjects {
-            iteration: self.iteration,
-            run_mode: self.run_mode,
-        };
-        self.tx_adapter.send_timed(&self.nn, &execution_objects);
+/// New networks (topology file, built-in) take their initial weights from `rng`
+fn load_network(args: &NetworkArgs, rng: &mut ChaCha8Rng) -> Result<Network, Box<dyn std::error::Error>> {
+    if let Some(path) = &args.topology {
+        let spec = TopologySpec::from_json(&fs::read_to_string(path)?)?;
+        return Ok(spec.build(rng)?);
     }
-
-    fn send_state_immidiately(&mut self) {
-        let execution_objects = ExecutionObjects {
-            iteration: self.iteration,
-            run_mode: self.run_mode,
-        };
-        self.tx_adapter.send(&self.nn, &execution_objects);
+    match &args.network {
+        Some(path) => model_file::load(path),
+        None => Ok(match args.builtin {
+            Builtin::KxB => build_nn1(rng),
+            Builtin::Ax2BxC => build_nn(rng),
+        }),
     }
+}
 
-    fn hang_out(&mut self) {
-        if self.run_mode == RunMode::Stepping || self.run_mode == RunMode::Pause {
-            loop {
-                if let Ok(event) = self.rx_events.recv_timeout(STEPPING_DURATION) {
-                    match event {
-                        Events::PauseRequested => {
-                            self.run_mode = RunMode::Pause;
-                            self.send_state_immidiately();
-                            break;
-                        },
-                        Events::SteppingRequested => {
-                            self.run_mode = RunMode::Stepping;
-                            break;
-                        }
-                        Events::PlayRequested => {
-                            self.run_mode = RunMode::Running;
-                            break;
-                        }
-                    }
-                } else {
-                    if self.run_mode == RunMode::Stepping {
-                        break;
-                    }
-                }
-            }
-        }
-        if self.run_mode == RunMode::Running {
-            if let Ok(event) = self.rx_events.try_recv() {
-                match event {
-                    Events::PauseRequested => {
-                        self.run_mode = RunMode::Pause;
-                        self.send_state_immidiately();
-                    },
-                    Events::SteppingRequested => self.run_mode = RunMode::Stepping,
-                    Events::PlayRequested => self.run_mode = RunMode::Running,
-                }
+/// Columns of the dataset file are bound to the input and output neurons of `nn`
+fn load_items(args: &DatasetArgs, nn: &Network, rng: &mut ChaCha8Rng) -> Result<Vec<Sample>, Box<dyn std::error::Error>> {
+    match &args.dataset {
+        Some(path) => Ok(Dataset::load(path, nn, &args.columns.clone().unwrap_or_default())?.samples),
+        None => {
+            let dataset = generators::generate(&generator_config(&args.generator)?, rng)?;
+            //сгенерированные столбцы привязываются по порядку
+            let (inputs, outputs) = (nn.layers[0].neurons.len(), nn.last().neurons.len());
+            if dataset.inputs.len() != inputs || dataset.outputs.len() != outputs {
+                return Err(format!("generator gives {:?} -> {:?}, the network has {inputs} inputs and {outputs} outputs",
+                                   dataset.inputs, dataset.outputs).into());
             }
+            Ok(dataset.samples)
         }
     }
+}
 
-    fn assert_not_nan(value: f32) {
-        if value.is_nan() {
-            panic!("Encountered NaN!");
-        }
+/// --generator-file or the defaults, overridden by the other options
+fn generator_config(args: &GeneratorArgs) -> Result<GeneratorConfig, Box<dyn std::error::Error>> {
+    let mut config: GeneratorConfig = match &args.generator_file {
+        Some(file) => serde_json::from_str(&fs::read_to_string(file)?)?,
+        None => GeneratorConfig::default(),
+    };
+    if let Some(function) = args.generator.clone() {
+        config.function = function;
     }
+    config.count = args.samples.unwrap_or(config.count);
+    config.min = args.min.unwrap_or(config.min);
+    config.max = args.max.unwrap_or(config.max);
+    config.integer = args.integer.unwrap_or(config.integer);
+    config.noise = args.noise.unwrap_or(config.noise);
+    Ok(config)
+}
 
-    fn loss(target: f32, value: f32) -> f32 {
-        let sign = (target - value).signum();
-        let t = target.abs();
-        let o = value.abs();
-        let diff = (t-o).abs();
-        diff/t.max(o)*sign
-    }
+/// A random seed when none is given, so that every run can be repeated
+fn resolve_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| rand::rng().random())
 }
 
-/*
-fn forward(nn: &mut Network, train_item: &TrainItem) {
-    nn.layers[0].neurons[0].output = train_item.a;
-    nn.layers[0].neurons[1].output = train_item.b;
-    nn.layers[0].neurons[3].output = train_item.c;
-
-    for layer_index in 1..nn.layers_count {
-        let (prev, current) = nn.layers.split_at_mut(layer_index);
-        let prev_layer = &prev[layer_index - 1];
-        let current_layer = &mut current[0];
-
-        for neuron in &mut current_layer.neurons {
-            if !neuron.is_dummy() {
-                let sum: f32 = neuron
-                    .input_links
-                    .iter()
-                    .filter(|link| !link.is_dummy())
-                    .map(|link| {
-                        return link.weight * prev_layer.get_value(&link.source_id);
-                    })
-                    .fold(0.0, |acc, e| acc + e);
-                neuron.sum_input = sum;
-                neuron.output = activation_functions::apply(&neuron.function_name, sum);
-            }
-        }
-    }
+/// nn.json -> nn.optimizer.json
+fn optimizer_state_path(network_path: &Path) -> PathBuf {
+    network_path.with_extension("optimizer.json")
 }
 
-fn backward(nn: &mut Network, train_item: &TrainItem, learning_rate: f32) {
-    let output_neuron_x1 = &nn.layers[nn.layers_count - 1].neurons[0];
-    let output_neuron_x2 = &nn.layers[nn.layers_count - 1].neurons[1];
-
-    let e_x1 = (train_item.x1 - output_neuron_x1.output)
-        * activation_functions::derivative(&output_neuron_x1.function_name, output_neuron_x1.sum_input);
-    let e_x2 = (train_item.x2 - output_neuron_x2.output)
-        * activation_functions::derivative(&output_neuron_x2.function_name, output_neuron_x2.sum_input);
-
-    nn.layers[nn.layers_count - 1].neurons[0].error = e_x1;
-    nn.layers[nn.layers_count - 1].neurons[1].error = e_x2;
-
-    for layer_index in (1..nn.layers_count).rev() {
-        let (prev, current) = nn.layers.split_at_mut(layer_index);
-        let prev_layer = &mut prev[layer_index - 1];
-        let current_layer = &mut current[0];
-        //weight updates
-        for neuron in &mut current_layer.neurons {
-            if !neuron.is_dummy() {
-                for link in neuron.input_links.iter_mut() {
-                    if !link.is_dummy() {
-                        let derive = activation_functions::derivative(&neuron.function_name, neuron.sum_input);
-                        let delta = neuron.error * derive * prev_layer.get_value(&link.source_id);
-                        link.weight += delta * learning_rate;
-                    }
-                }
-            }
-        }
-        //error updates
-        for prev_neuron in &mut prev_layer.neurons {
-            if prev_neuron.is_dummy() {
-                continue;
-            }
+/// nn.json -> nn.checkpoint.json
+fn checkpoint_path(network_path: &Path) -> PathBuf {
+    network_path.with_extension("checkpoint.json")
+}
 
-            let mut error_sum = 0.0;
-            for neuron in &current_layer.neurons {
-                if neuron.is_dummy() {
-                    continue;
-                }
-                for link in &neuron.input_links {
-                    if link.source_id == prev_neuron.id {
-                        error_sum += link.weight * neuron.error;
-                    }
-                }
-            }
-            prev_neuron.error = error_sum * activation_functions::derivative(&prev_neuron.function_name, prev_neuron.sum_input);
-        }
+#[cfg(test)]
+mod tests {
+    use crate::cli::{Cli, Command};
+    use crate::{model_file, train};
+    use clap::Parser;
+    use std::fs;
+    use std::path::Path;
+
+    fn run_train(args: &[&str]) {
+        let Command::Train(args) = Cli::try_parse_from([&["square-eq-nn", "train", "--headless"], args].concat()).unwrap().command else {
+            panic!("not a train command");
+        };
+        train(*args).unwrap();
     }
-}
 
+    fn weights(path: &Path) -> Vec<f32> {
+        let nn = model_file::load(path).unwrap();
+        nn.layers.iter().flat_map(|l| l.neurons.iter())
+            .flat_map(|n| n.input_links.iter().map(|l| l.weight).chain([n.bias]))
+            .collect()
+    }
 
-    let mut train_items: Vec<TrainItem> = load_train()?;
-    shuffle(&mut train_items);
-for item in & train_items {
-    forward(&mut nn, &item);
-    backward(&mut nn, &item, learning_rate);
-}
+    #[test]
+    fn resume_takes_optimizer_from_checkpoint() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["square-eq-nn", "train", "--resume", "nn.checkpoint.json"], args].concat());
+        assert!(parse(&[]).is_ok());
+        for flag in [["--learning-rate", "0.1"], ["--optimizer", "adam"], ["--lr-schedule", "cosine"], ["--lr-schedule-file", "lr.json"]] {
+            assert!(parse(&flag).is_err(), "{flag:?}");
+        }
+    }
 
-let mut total_loss_x1 = 0.0;
-let mut total_loss_x2 = 0.0;
+    #[test]
+    fn dense_backend_only_headless() {
+        let Command::Train(args) = Cli::try_parse_from(["square-eq-nn", "train", "--backend", "dense"]).unwrap().command else {
+            panic!("not a train command");
+        };
+        assert_eq!(train(*args).unwrap_err().to_string(), "--backend dense has no UI, use it with --headless");
+        assert!(Cli::try_parse_from(["square-eq-nn", "train", "--backend", "graph"]).is_ok());
+    }
 
-for item in &train_items {
-    forward(&mut nn, item);
-    let x1_out = nn.last().neurons[0].output;
-    let x2_out = nn.last().neurons[1].output;
-    total_loss_x1 += loss(item.x1, x1_out);
-    total_loss_x2 += loss(item.x2, x2_out);
+    #[test]
+    fn resume_at_final_epoch_does_not_train() {
+        let dir = std::env::temp_dir().join(format!("square-eq-nn-{}-resume-final", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let (trained, checkpoint, resumed) = (path("nn.json"), path("nn.checkpoint.json"), path("resumed.json"));
+
+        run_train(&["--seed", "1", "--epochs", "2", "--target-error", "0", "--checkpoint-every", "1", "--output", &trained]);
+        run_train(&["--resume", &checkpoint, "--epochs", "2", "--target-error", "0", "--checkpoint", &checkpoint, "--output", &resumed]);
+        let (trained, resumed) = (weights(Path::new(&trained)), weights(Path::new(&resumed)));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(trained, resumed);
+    }
 }
-
-let avg_loss_x1 = total_loss_x1 / train_items.len() as f32;
-let avg_loss_x2 = total_loss_x2 / train_items.len() as f32;
-*/
@@ -0,0 +1,221 @@
+//! Compact model file:
+//! * magic `SQNN`, format version (u32)
+//! * architecture length (u32) and the architecture as compact json: ids, activations, link sources, normalization
+//! * parameter count (u32) and the parameters, f32 each, in the order of
+//!   [`crate::backpropagation::accumulate_gradients`]: the links of a neuron, then its bias
+//!
+//! All numbers are little-endian.
+use crate::model_file::{LayerModel, ModelFile, NeuronModel, FORMAT_VERSION};
+use crate::nn_objects::{ActivationFunction, Link, NetworkMetadata};
+use crate::normalization::Normalization;
+use serde::{Deserialize, Serialize};
+
+pub const MAGIC: &[u8; 4] = b"SQNN";
+
+#[derive(Serialize, Deserialize)]
+struct Architecture {
+    created_at: u64,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    metadata: NetworkMetadata,
+    #[serde(default)]
+    normalization: Option<Normalization>,
+    layers: Vec<LayerArchitecture>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayerArchitecture {
+    dropout: f32,
+    neurons: Vec<NeuronArchitecture>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NeuronArchitecture {
+    id: String,
+    activation: ActivationFunction,
+    sources: Vec<String>,
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn to_bytes(model: &ModelFile) -> Result<Vec<u8>, serde_json::Error> {
+    let mut parameters = vec![];
+    let architecture = Architecture {
+        created_at: model.created_at,
+        inputs: model.inputs.clone(),
+        outputs: model.outputs.clone(),
+        metadata: model.metadata.clone(),
+        normalization: model.normalization.clone(),
+        layers: model.layers.iter().enumerate()
+            .map(|(index, layer)| LayerArchitecture {
+                dropout: layer.dropout,
+                neurons: layer.neurons.iter()
+                    .map(|n| {
+                        //у входных нейронов нет параметров
+                        if index > 0 {
+                            parameters.extend(n.links.iter().map(|l| l.weight));
+                            parameters.push(n.bias);
+                        }
+                        NeuronArchitecture {
+                            id: n.id.clone(),
+                            activation: n.activation.clone(),
+                            sources: n.links.iter().map(|l| l.source_id.clone()).collect(),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    let architecture = serde_json::to_vec(&architecture)?;
+
+    let mut bytes = Vec::with_capacity(16 + architecture.len() + parameters.len() * 4);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&model.format_version.to_le_bytes());
+    bytes.extend_from_slice(&(architecture.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&architecture);
+    bytes.extend_from_slice(&(parameters.len() as u32).to_le_bytes());
+    for parameter in parameters {
+        bytes.extend_from_slice(&parameter.to_le_bytes());
+    }
+    Ok(bytes)
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<ModelFile, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err("not a binary model file".to_string());
+    }
+    let format_version = reader.u32()?;
+    if format_version > FORMAT_VERSION {
+        return Err(format!("format version {format_version} is newer than the supported {FORMAT_VERSION}"));
+    }
+    let architecture_length = reader.u32()? as usize;
+    let architecture: Architecture = serde_json::from_slice(reader.take(architecture_length)?)
+        .map_err(|e| format!("architecture: {e}"))?;
+    let parameters_count = reader.u32()? as usize;
+    //количество проверяем до выделения памяти: в повреждённом файле там может быть что угодно
+    let expected: usize = architecture.layers.iter().skip(1)
+        .flat_map(|l| l.neurons.iter())
+        .map(|n| n.sources.len() + 1)
+        .sum();
+    if expected != parameters_count {
+        return Err(format!("architecture has {expected} parameters, file {parameters_count}"));
+    }
+    let available = (bytes.len() - reader.position) / 4;
+    if parameters_count > available {
+        return Err(format!("file is truncated: {parameters_count} parameters, {available} in the file"));
+    }
+    let mut parameters = Vec::with_capacity(parameters_count);
+    for _ in 0..parameters_count {
+        parameters.push(f32::from_le_bytes(reader.take(4)?.try_into().unwrap()));
+    }
+    if reader.position != bytes.len() {
+        return Err(format!("{} unexpected bytes after the parameters", bytes.len() - reader.position));
+    }
+
+    let mut parameters = parameters.into_iter();
+    let layers = architecture.layers.into_iter().enumerate()
+        .map(|(index, layer)| LayerModel {
+            dropout: layer.dropout,
+            neurons: layer.neurons.into_iter()
+                .map(|n| {
+                    let mut next = || if index > 0 { parameters.next().unwrap() } else { 0.0 };
+                    let links = n.sources.into_iter().map(|source| Link::new(source, next())).collect();
+                    NeuronModel { id: n.id, activation: n.activation, links, bias: next() }
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(ModelFile {
+        format_version,
+        created_at: architecture.created_at,
+        inputs: architecture.inputs,
+        outputs: architecture.outputs,
+        metadata: architecture.metadata,
+        normalization: architecture.normalization,
+        layers,
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(format!("file is truncated: {} bytes, expected at least {end}", self.bytes.len()));
+        }
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model_binary::from_bytes;
+    use crate::model_file::{from_bytes as load_bytes, to_bytes, to_json, from_json};
+    use crate::nn_build::build_nn;
+    use crate::nn_objects::Network;
+    use crate::normalization::{Normalization, Scaler};
+
+    fn parameters(nn: &Network) -> Vec<u32> {
+        nn.layers.iter()
+            .flat_map(|l| l.neurons.iter())
+            .flat_map(|n| n.input_links.iter().map(|l| l.weight).chain([n.bias]))
+            .map(f32::to_bits)
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_matches_json() {
+        let mut nn = build_nn(&mut rand::rng());
+        nn.layers[2].neurons[1].bias = -0.125;
+        nn.layers[1].neurons[3].input_links[0].weight = 1.0 / 3.0;
+        nn.metadata.seed = Some(11);
+        nn.normalization = Some(Normalization { inputs: vec![Scaler::Log; 3], outputs: vec![Scaler::ZScore { mean: 0.5, std_dev: 3.0 }; 2] });
+        let json = to_json(&nn).unwrap();
+        let bytes = to_bytes(&nn).unwrap();
+        assert!(bytes.len() < json.len());
+
+        let from_json = from_json(&json).unwrap();
+        let from_binary = load_bytes(&bytes).unwrap();
+        assert_eq!(parameters(&from_binary), parameters(&nn));
+        assert_eq!(parameters(&from_binary), parameters(&from_json));
+        assert_eq!(from_binary.metadata.seed, Some(11));
+        assert_eq!(from_binary.normalization, nn.normalization);
+        let ids = |nn: &Network| -> Vec<String> {
+            nn.layers.iter().flat_map(|l| l.neurons.iter().map(|n| n.id.clone())).collect()
+        };
+        assert_eq!(ids(&from_binary), ids(&from_json));
+    }
+
+    #[test]
+    fn broken_files() {
+        let bytes = to_bytes(&build_nn(&mut rand::rng())).unwrap();
+        assert!(from_bytes(&bytes[..bytes.len() - 2]).unwrap_err().starts_with("file is truncated"));
+        assert_eq!(from_bytes(b"JSON").unwrap_err(), "not a binary model file");
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert_eq!(from_bytes(&extra).unwrap_err(), "1 unexpected bytes after the parameters");
+
+        let parameters = build_nn(&mut rand::rng()).layers.iter().skip(1)
+            .flat_map(|l| l.neurons.iter())
+            .map(|n| n.input_links.len() + 1)
+            .sum::<usize>();
+        let count_at = bytes.len() - parameters * 4 - 4;
+        let mut corrupt = bytes.clone();
+        corrupt[count_at..count_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(from_bytes(&corrupt).unwrap_err(), format!("architecture has {parameters} parameters, file {}", u32::MAX));
+    }
+}
@@ -0,0 +1,228 @@
+//! Saved model: architecture and trained parameters, without the runtime state of the neurons
+//! (`output`, `sum_input`, `error`, `delta`).
+//! Files without `format_version` are read as a plain [`Network`] dump (see [`Network::from_json`]).
+//! The same model can be stored in binary, see [`crate::model_binary`].
+use crate::model_binary;
+use crate::validation::describe;
+use crate::nn_objects::{ActivationFunction, Layer, Link, Network, NetworkMetadata, Neuron};
+use crate::normalization::Normalization;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelFile {
+    pub format_version: u32,
+    /// Unix time, seconds
+    pub created_at: u64,
+    /// Ids of the input neurons, in the order of the input values
+    pub inputs: Vec<String>,
+    /// Ids of the output neurons
+    pub outputs: Vec<String>,
+    pub metadata: NetworkMetadata,
+    /// Applied to the inputs and outputs at inference, absent when the values are used as they are
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization: Option<Normalization>,
+    pub layers: Vec<LayerModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerModel {
+    pub neurons: Vec<NeuronModel>,
+    #[serde(default)]
+    pub dropout: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeuronModel {
+    pub id: String,
+    pub activation: ActivationFunction,
+    #[serde(default)]
+    pub bias: f32,
+    #[serde(default)]
+    pub links: Vec<Link>,
+}
+
+impl ModelFile {
+    pub fn from_network(nn: &Network) -> Self {
+        let ids = |layer: &Layer| layer.neurons.iter().map(|n| n.id.clone()).collect();
+        ModelFile {
+            format_version: FORMAT_VERSION,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            inputs: nn.layers.first().map(ids).unwrap_or_default(),
+            outputs: nn.layers.last().map(ids).unwrap_or_default(),
+            metadata: nn.metadata.clone(),
+            normalization: nn.normalization.clone(),
+            layers: nn.layers.iter()
+                .map(|layer| LayerModel {
+                    neurons: layer.neurons.iter()
+                        .map(|n| NeuronModel {
+                            id: n.id.clone(),
+                            activation: n.function_name.clone(),
+                            bias: n.bias,
+                            links: n.input_links.clone(),
+                        })
+                        .collect(),
+                    dropout: layer.dropout,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn into_network(self) -> Result<Network, String> {
+        let layers = self.layers.into_iter().enumerate()
+            .map(|(index, layer)| {
+                let neurons = layer.neurons.into_iter()
+                    .map(|n| {
+                        if index == 0 {
+                            //связи входов сохраняем, чтобы validate() сообщил о них
+                            let mut neuron = Neuron::new_input(n.id);
+                            neuron.input_links = n.links;
+                            return neuron;
+                        }
+                        let mut neuron = Neuron::new_middle(n.id, 0.0, n.activation, n.links);
+                        neuron.bias = n.bias;
+                        neuron
+                    })
+                    .collect();
+                let mut result = Layer::new(neurons);
+                result.dropout = layer.dropout;
+                result
+            })
+            .collect();
+        let mut nn = Network::new(layers);
+        nn.metadata = self.metadata;
+        nn.normalization = self.normalization;
+
+        let ids = |layer: Option<&Layer>| -> Vec<String> {
+            layer.map(|l| l.neurons.iter().map(|n| n.id.clone()).collect()).unwrap_or_default()
+        };
+        if ids(nn.layers.first()) != self.inputs {
+            return Err(format!("inputs {:?} do not match the first layer {:?}", self.inputs, ids(nn.layers.first())));
+        }
+        if ids(nn.layers.last()) != self.outputs {
+            return Err(format!("outputs {:?} do not match the last layer {:?}", self.outputs, ids(nn.layers.last())));
+        }
+        if let Some(normalization) = &nn.normalization
+            && (normalization.inputs.len() != self.inputs.len() || normalization.outputs.len() != self.outputs.len()) {
+            return Err(format!("normalization has {} input and {} output scalers, the network {} inputs and {} outputs",
+                               normalization.inputs.len(), normalization.outputs.len(), self.inputs.len(), self.outputs.len()));
+        }
+        Ok(nn)
+    }
+}
+
+pub fn to_json(nn: &Network) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&ModelFile::from_network(nn))
+}
+
+pub fn to_bytes(nn: &Network) -> Result<Vec<u8>, serde_json::Error> {
+    model_binary::to_bytes(&ModelFile::from_network(nn))
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<Network, String> {
+    let nn = model_binary::from_bytes(bytes)?.into_network()?;
+    nn.validate().map_err(|errors| describe(&errors))?;
+    Ok(nn)
+}
+
+/// Reads both the versioned format and plain [`Network`] dumps, the result is validated
+pub fn from_json(json: &str) -> Result<Network, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let nn = match value.get("format_version") {
+        Some(version) => {
+            let version = version.as_u64().ok_or(format!("format_version is not a number: {version}"))?;
+            if version > FORMAT_VERSION as u64 {
+                return Err(format!("format version {version} is newer than the supported {FORMAT_VERSION}"));
+            }
+            let model: ModelFile = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            model.into_network()?
+        }
+        None => Network::from_json(json)?,
+    };
+    nn.validate().map_err(|errors| describe(&errors))?;
+    Ok(nn)
+}
+
+/// Binary for `.bin` files, json otherwise
+pub fn save(nn: &Network, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if path.extension().is_some_and(|e| e == "bin") {
+        fs::write(path, to_bytes(nn)?)?;
+    } else {
+        fs::write(path, to_json(nn)?)?;
+    }
+    Ok(())
+}
+
+/// The format is recognized by the content, not by the extension
+pub fn load(path: &Path) -> Result<Network, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let nn = if model_binary::is_binary(&bytes) {
+        from_bytes(&bytes)
+    } else {
+        String::from_utf8(bytes).map_err(|e| e.to_string()).and_then(|json| from_json(&json))
+    };
+    Ok(nn.map_err(|e| format!("{}: {e}", path.display()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model_file::{from_bytes, from_json, to_bytes, to_json, ModelFile};
+    use crate::nn_build::build_nn;
+    use crate::nn_objects::Link;
+    use crate::normalization::{Normalization, Scaler};
+
+    #[test]
+    fn round_trip() {
+        let mut nn = build_nn(&mut rand::rng());
+        nn.layers[1].neurons[0].bias = 0.25;
+        nn.layers[1].neurons[0].output = 3.0;
+        nn.metadata.final_loss = Some(0.5);
+        nn.normalization = Some(Normalization {
+            inputs: vec![Scaler::MinMax { min: -10.0, max: 10.0 }, Scaler::Log, Scaler::Identity],
+            outputs: vec![Scaler::ZScore { mean: 1.0, std_dev: 2.0 }, Scaler::Identity],
+        });
+        let json = to_json(&nn).unwrap();
+        assert!(!json.contains("sum_input"));
+
+        let loaded = from_json(&json).unwrap();
+        assert_eq!(loaded.layers_count, nn.layers_count);
+        assert_eq!(loaded.layers[1].neurons[0].bias, 0.25);
+        assert_eq!(loaded.layers[1].neurons[0].output, 0.0);
+        assert_eq!(loaded.metadata.final_loss, Some(0.5));
+        assert_eq!(loaded.normalization, nn.normalization);
+        assert_eq!(loaded.last().neurons[1].input_links[2].weight, nn.last().neurons[1].input_links[2].weight);
+    }
+
+    #[test]
+    fn readable_errors() {
+        let nn = build_nn(&mut rand::rng());
+
+        let mut dangling = nn.clone();
+        dangling.layers[2].neurons[0].input_links.push(Link::new("q".to_string(), 1.0));
+        assert_eq!(from_json(&to_json(&dangling).unwrap()).unwrap_err(),
+                   "neuron n1 has a link from q, which is not in the network");
+
+        let mut duplicate = nn.clone();
+        duplicate.layers[3].neurons[1].id = "m1".to_string();
+        assert_eq!(from_json(&to_json(&duplicate).unwrap()).unwrap_err(), "duplicate neuron id: m1");
+
+        let mut input_links = nn.clone();
+        input_links.layers[0].neurons[1].input_links.push(Link::new("c".to_string(), 1.0));
+        assert_eq!(from_json(&to_json(&input_links).unwrap()).unwrap_err(), "input neuron b has links");
+        assert_eq!(from_bytes(&to_bytes(&input_links).unwrap()).unwrap_err(), "input neuron b has links");
+
+        let mut newer = ModelFile::from_network(&nn);
+        newer.format_version = 99;
+        assert_eq!(from_json(&serde_json::to_string(&newer).unwrap()).unwrap_err(),
+                   "format version 99 is newer than the supported 1");
+
+        let mut legacy = nn.clone();
+        legacy.layers_count = 7;
+        assert_eq!(from_json(&serde_json::to_string(&legacy).unwrap()).unwrap_err(),
+                   "layers_count is 7, but there are 4 layers");
+    }
+}
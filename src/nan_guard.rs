@@ -0,0 +1,108 @@
+//! Detection of a diverging run: NaN or infinite values in the network after a train step
+use crate::dataset::Sample;
+use crate::nn_objects::Network;
+use std::fmt;
+
+/// Value that is not finite. The parameters are the likely cause, so they are checked first,
+/// then the outputs in forward order and the errors in backward order
+#[derive(Debug, Clone, PartialEq)]
+pub enum NonFinite {
+    Weight { source: String, neuron: String, value: f32 },
+    Bias { neuron: String, value: f32 },
+    Output { neuron: String, value: f32 },
+    Error { neuron: String, value: f32 },
+}
+
+impl NonFinite {
+    /// Neuron to highlight, for a link the one it goes to
+    pub fn neuron(&self) -> &str {
+        match self {
+            NonFinite::Weight { neuron, .. } | NonFinite::Bias { neuron, .. }
+            | NonFinite::Output { neuron, .. } | NonFinite::Error { neuron, .. } => neuron,
+        }
+    }
+
+    /// (source, neuron) of the link
+    pub fn link(&self) -> Option<(&str, &str)> {
+        match self {
+            NonFinite::Weight { source, neuron, .. } => Some((source, neuron)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NonFinite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonFinite::Weight { source, neuron, value } => write!(f, "weight of {source}->{neuron} is {value}"),
+            NonFinite::Bias { neuron, value } => write!(f, "bias of {neuron} is {value}"),
+            NonFinite::Output { neuron, value } => write!(f, "output of {neuron} is {value}"),
+            NonFinite::Error { neuron, value } => write!(f, "error of {neuron} is {value}"),
+        }
+    }
+}
+
+pub fn find_non_finite(nn: &Network) -> Option<NonFinite> {
+    let neurons = || nn.layers.iter().flat_map(|l| l.neurons.iter());
+    for neuron in neurons() {
+        if let Some(link) = neuron.input_links.iter().find(|l| !l.weight.is_finite()) {
+            return Some(NonFinite::Weight { source: link.source_id.clone(), neuron: neuron.id.clone(), value: link.weight });
+        }
+        if !neuron.bias.is_finite() {
+            return Some(NonFinite::Bias { neuron: neuron.id.clone(), value: neuron.bias });
+        }
+    }
+    if let Some(neuron) = neurons().find(|n| !n.output.is_finite()) {
+        return Some(NonFinite::Output { neuron: neuron.id.clone(), value: neuron.output });
+    }
+    let mut backward = nn.layers.iter().rev().flat_map(|l| l.neurons.iter());
+    backward.find(|n| !n.error.is_finite())
+        .map(|neuron| NonFinite::Error { neuron: neuron.id.clone(), value: neuron.error })
+}
+
+/// Train step that produced a non-finite value
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub problem: NonFinite,
+    /// Epoch being trained, from 1
+    pub epoch: usize,
+    /// Index of the sample in the epoch, the first one of the mini-batch for the dense backend
+    pub sample: usize,
+    pub iteration: usize,
+    pub item: Sample,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at epoch {}, sample {} (iteration {}), inputs {:?}, targets {:?}",
+               self.problem, self.epoch, self.sample, self.iteration, self.item.inputs, self.item.outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nan_guard::{find_non_finite, NonFinite};
+    use crate::nn_build::build_nn;
+
+    #[test]
+    fn first_non_finite_value() {
+        let mut nn = build_nn(&mut rand::rng());
+        assert_eq!(find_non_finite(&nn), None);
+
+        nn.layers[3].neurons[1].error = f32::NAN;
+        nn.layers[2].neurons[0].output = f32::INFINITY;
+        let found = find_non_finite(&nn).unwrap();
+        assert_eq!(found, NonFinite::Output { neuron: "n1".to_string(), value: f32::INFINITY });
+        assert_eq!(found.to_string(), "output of n1 is inf");
+
+        nn.layers[2].neurons[0].output = 0.0;
+        nn.layers[0].neurons[0].error = f32::NAN;
+        assert_eq!(find_non_finite(&nn).unwrap().neuron(), "x2");
+
+        let source = nn.layers[1].neurons[2].input_links[1].source_id.clone();
+        nn.layers[1].neurons[2].input_links[1].weight = f32::NAN;
+        let found = find_non_finite(&nn).unwrap();
+        assert_eq!(found.link(), Some((source.as_str(), "m3")));
+        assert_eq!(found.neuron(), "m3");
+    }
+}
@@ -1,8 +1,8 @@
+use crate::execution_plan::ExecutionPlan;
+use crate::normalization::Normalization;
 use serde::{Deserialize, Serialize};
-
-pub const MAX_LINKS: usize = 4;
-pub const MAX_NEURONS_PER_LAYER: usize = 4;
-pub const MAX_LAYERS_COUNT: usize = 7;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Link {
@@ -16,13 +16,8 @@ impl Link {
             weight
         }
     }
-    pub fn new_dummy() -> Self {
-        Link {
-            source_id: "".to_string(),
-            weight: 0.0,
-        }
-    }
-    pub fn is_dummy(&self) -> bool {
+    /// Padding link of the old fixed-size format (empty `source_id`)
+    fn is_legacy_padding(&self) -> bool {
         self.source_id.is_empty()
     }
 }
@@ -37,14 +32,47 @@ pub enum ActivationFunction {
     Relu
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum LossFunction {
+    Mse,
+    Mae,
+    Huber { delta: f32 },
+    LogCosh,
+    #[default]
+    Relative,
+}
+
+impl FromStr for LossFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mse" => Ok(LossFunction::Mse),
+            "mae" => Ok(LossFunction::Mae),
+            "huber" => Ok(LossFunction::Huber { delta: 1.0 }),
+            "log-cosh" | "logcosh" => Ok(LossFunction::LogCosh),
+            "relative" => Ok(LossFunction::Relative),
+            _ => Err(format!("unknown loss function: {s}")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Neuron {
     pub id: String,
     pub output: f32,
     pub sum_input: f32,
+    /// -dL/d(output), see [`crate::backpropagation`]
     pub error: f32,
+    /// -dL/d(sum_input), see [`crate::backpropagation`]
+    #[serde(default)]
+    pub delta: f32,
     pub function_name: ActivationFunction,
-    pub input_links: [Link; MAX_LINKS],
+    #[serde(default)]
+    pub input_links: Vec<Link>,
+    /// Trainable offset added to `sum_input`, not used by input neurons
+    #[serde(default)]
+    pub bias: f32,
 }
 
 impl Neuron {
@@ -54,60 +82,172 @@ impl Neuron {
             output: 0.0,
             sum_input: 0.0,
             error: 1.0,
+            delta: 0.0,
             function_name: ActivationFunction::None,
-            input_links: std::array::from_fn(|_| Link::new_dummy()),
+            input_links: vec![],
+            bias: 0.0,
         }
     }
-    pub fn new_middle(id: String, value: f32, function: ActivationFunction, link: [Link; MAX_LINKS]) -> Self {
+    pub fn new_middle(id: String, value: f32, function: ActivationFunction, links: Vec<Link>) -> Self {
         Neuron {
             id,
             output: value,
             sum_input: 0.0,
             error: 1.0,
+            delta: 0.0,
             function_name: function,
-            input_links: link,
+            input_links: links,
+            bias: 0.0,
         }
     }
-    pub fn new_dummy() -> Self {
-        Neuron {
-            id: "".to_string(), // empty string here
-            output: 0.0,
-            sum_input: 0.0,
-            error: 1.0,
-            function_name: ActivationFunction::None,
-            input_links: std::array::from_fn(|_| Link::new_dummy()),
-        }
-    }
-    pub fn is_dummy(&self) -> bool {
+    /// Padding neuron of the old fixed-size format (empty `id`)
+    fn is_legacy_padding(&self) -> bool {
         self.id.is_empty()
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layer {
-    pub neurons: [Neuron; MAX_NEURONS_PER_LAYER],
+    pub neurons: Vec<Neuron>,
+    /// Probability to drop a neuron of this layer while training
+    #[serde(default)]
+    pub dropout: f32,
+    /// Output multipliers of the current train sample: 0 - dropped, 1/(1-dropout) - kept.
+    /// Empty when nothing is dropped
+    #[serde(skip)]
+    pub dropout_mask: Vec<f32>,
 }
 
 impl Layer {
-    pub fn new_dummy() -> Self {
-        Layer {
-            neurons: std::array::from_fn(|_| Neuron::new_dummy()),
-        }
+    pub fn new(neurons: Vec<Neuron>) -> Self {
+        Layer { neurons, dropout: 0.0, dropout_mask: vec![] }
     }
+    /// Output of a neuron looked up by id, the passes use [`Network::compile_plan`]
+    #[cfg(test)]
     pub fn get_value(&self, neuron_id: &String) -> f32 {
         self.neurons.iter()
             .find(|n| n.id.eq(neuron_id)).unwrap().output
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Network {
-    pub layers: [Layer; MAX_LAYERS_COUNT],
+    pub layers: Vec<Layer>,
     pub layers_count: usize,
+    #[serde(default)]
+    pub metadata: NetworkMetadata,
+    /// Scaling of the inputs and outputs, see [`crate::normalization`]
+    #[serde(default)]
+    pub normalization: Option<Normalization>,
+    /// Compiled on the first pass, see [`Network::compile_plan`]
+    #[serde(skip)]
+    plan: Option<Arc<ExecutionPlan>>,
+}
+
+/// The plan is not copied: a copy is usually changed before it runs
+impl Clone for Network {
+    fn clone(&self) -> Self {
+        Network {
+            layers: self.layers.clone(),
+            layers_count: self.layers_count,
+            metadata: self.metadata.clone(),
+            normalization: self.normalization.clone(),
+            plan: None,
+        }
+    }
+}
+
+/// How the network was trained
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkMetadata {
+    pub loss: LossFunction,
+    /// Seed of the run that produced the weights
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Train data: file name or generator
+    #[serde(default)]
+    pub dataset: Option<String>,
+    /// Validation error when training stopped
+    #[serde(default)]
+    pub final_loss: Option<f32>,
 }
 
 impl Network {
+    pub fn new(layers: Vec<Layer>) -> Self {
+        let layers_count = layers.len();
+        Network { layers, layers_count, metadata: NetworkMetadata::default(), normalization: None, plan: None }
+    }
+
+    /// Compiles the plan on first use and again when the neuron or link counts change.
+    /// Code that rewires links without changing the counts has to call [`Network::invalidate_plan`]
+    pub fn compile_plan(&mut self) -> Result<Arc<ExecutionPlan>, String> {
+        match &self.plan {
+            Some(plan) if plan.matches(self) => Ok(plan.clone()),
+            _ => {
+                let plan = Arc::new(ExecutionPlan::compile(self)?);
+                self.plan = Some(plan.clone());
+                Ok(plan)
+            }
+        }
+    }
+
+    pub fn invalidate_plan(&mut self) {
+        self.plan = None;
+    }
+
+    /// Number of trainable parameters: link weights and biases of non-input neurons
+    pub fn parameters_count(&self) -> usize {
+        self.layers.iter().skip(1)
+            .flat_map(|l| l.neurons.iter())
+            .map(|n| n.input_links.len() + 1)
+            .sum()
+    }
+
     pub fn last(&self) -> &Layer {
         &self.layers[self.layers_count-1]
     }
-}
\ No newline at end of file
+
+    /// Loads a network from a plain serde dump, see [`crate::model_file`] for the versioned format.
+    /// Files written with the old fixed-size arrays (MAX_LINKS/MAX_NEURONS_PER_LAYER/MAX_LAYERS_COUNT)
+    /// are migrated: padding layers, neurons and links are dropped.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut nn: Network = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if nn.layers_count > nn.layers.len() {
+            return Err(format!("layers_count is {}, but there are {} layers", nn.layers_count, nn.layers.len()));
+        }
+        nn.drop_legacy_padding();
+        Ok(nn)
+    }
+
+    fn drop_legacy_padding(&mut self) {
+        self.layers.truncate(self.layers_count);
+        for layer in self.layers.iter_mut() {
+            layer.neurons.retain(|n| !n.is_legacy_padding());
+            for neuron in layer.neurons.iter_mut() {
+                neuron.input_links.retain(|l| !l.is_legacy_padding());
+            }
+        }
+        self.layers.retain(|l| !l.neurons.is_empty());
+        self.layers_count = self.layers.len();
+        self.invalidate_plan();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn_objects::Network;
+    use std::fs;
+
+    #[test]
+    fn load_legacy_fixed_size_nn() {
+        let json = fs::read_to_string("neural-networks/kx_b/nn.json").unwrap();
+        let nn = Network::from_json(&json).unwrap();
+        assert_eq!(nn.layers_count, 3);
+        assert_eq!(nn.layers.len(), 3);
+        let sizes: Vec<usize> = nn.layers.iter().map(|l| l.neurons.len()).collect();
+        assert_eq!(sizes, vec![3, 2, 1]);
+        assert!(nn.layers[0].neurons.iter().all(|n| n.input_links.is_empty()));
+        assert_eq!(nn.last().neurons[0].input_links.len(), 2);
+        assert_eq!(nn.last().neurons[0].bias, 0.0);
+    }
+}
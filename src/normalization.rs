@@ -0,0 +1,187 @@
+//! Preprocessing of the values going in and out of a network. Scalers are fitted on the train data
+//! and saved with the model: [`set_inputs`](crate::execution_context::set_inputs) scales the inputs,
+//! [`Network::predictions`] brings the outputs back to the original units.
+use crate::dataset::Sample;
+use crate::nn_objects::Network;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How to fit a [`Scaler`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Scaling {
+    None,
+    MinMax,
+    ZScore,
+    Log,
+}
+
+impl FromStr for Scaling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Scaling::None),
+            "min-max" | "minmax" => Ok(Scaling::MinMax),
+            "z-score" | "zscore" => Ok(Scaling::ZScore),
+            "log" => Ok(Scaling::Log),
+            _ => Err(format!("unknown scaling: {s}")),
+        }
+    }
+}
+
+/// Fitted transformation of one value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Scaler {
+    Identity,
+    /// min..max -> 0..1
+    MinMax { min: f32, max: f32 },
+    /// (x - mean) / std_dev
+    ZScore { mean: f32, std_dev: f32 },
+    /// sign(x) ln(1 + |x|), keeps the sign so negative values are allowed
+    Log,
+}
+
+impl Scaler {
+    /// Values without a target (NaN) are ignored, a column without values is left as is
+    pub fn fit(scaling: Scaling, values: &[f32]) -> Self {
+        let values: Vec<f32> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        match scaling {
+            Scaling::None => Scaler::Identity,
+            Scaling::MinMax | Scaling::ZScore if values.is_empty() => Scaler::Identity,
+            Scaling::MinMax => Scaler::MinMax {
+                min: values.iter().copied().fold(f32::MAX, f32::min),
+                max: values.iter().copied().fold(f32::MIN, f32::max),
+            },
+            Scaling::ZScore => {
+                let count = values.len().max(1) as f32;
+                let mean = values.iter().sum::<f32>() / count;
+                let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / count;
+                Scaler::ZScore { mean, std_dev: variance.sqrt() }
+            }
+            Scaling::Log => Scaler::Log,
+        }
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            Scaler::Identity => value,
+            Scaler::MinMax { min, max } => (value - min) / non_zero(max - min),
+            Scaler::ZScore { mean, std_dev } => (value - mean) / non_zero(*std_dev),
+            Scaler::Log => value.signum() * value.abs().ln_1p(),
+        }
+    }
+
+    pub fn invert(&self, value: f32) -> f32 {
+        match self {
+            Scaler::Identity => value,
+            Scaler::MinMax { min, max } => min + value * non_zero(max - min),
+            Scaler::ZScore { mean, std_dev } => mean + value * non_zero(*std_dev),
+            Scaler::Log => value.signum() * value.abs().exp_m1(),
+        }
+    }
+}
+
+/// A constant column is only shifted
+fn non_zero(range: f32) -> f32 {
+    if range.abs() < f32::EPSILON { 1.0 } else { range }
+}
+
+/// One scaler per input and per output neuron
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Normalization {
+    pub inputs: Vec<Scaler>,
+    pub outputs: Vec<Scaler>,
+}
+
+impl Normalization {
+    pub fn fit(samples: &[Sample], inputs: Scaling, outputs: Scaling) -> Self {
+        let width = |values: fn(&Sample) -> &Vec<f32>| samples.iter().map(|s| values(s).len()).min().unwrap_or(0);
+        let column = |values: fn(&Sample) -> &Vec<f32>, index: usize| -> Vec<f32> {
+            samples.iter().map(|s| values(s)[index]).collect()
+        };
+        Normalization {
+            inputs: (0..width(|s| &s.inputs)).map(|i| Scaler::fit(inputs, &column(|s| &s.inputs, i))).collect(),
+            outputs: (0..width(|s| &s.outputs)).map(|i| Scaler::fit(outputs, &column(|s| &s.outputs, i))).collect(),
+        }
+    }
+}
+
+fn apply(scalers: Option<&Vec<Scaler>>, values: &[f32], invert: bool) -> Vec<f32> {
+    values.iter().enumerate()
+        .map(|(index, &value)| match scalers.and_then(|s| s.get(index)) {
+            Some(scaler) if invert => scaler.invert(value),
+            Some(scaler) => scaler.apply(value),
+            None => value,
+        })
+        .collect()
+}
+
+impl Network {
+    /// Raw input values as the network sees them
+    pub fn normalize_inputs(&self, inputs: &[f32]) -> Vec<f32> {
+        apply(self.normalization.as_ref().map(|n| &n.inputs), inputs, false)
+    }
+
+    /// Input values of the network back in the original units
+    pub fn denormalize_inputs(&self, inputs: &[f32]) -> Vec<f32> {
+        apply(self.normalization.as_ref().map(|n| &n.inputs), inputs, true)
+    }
+
+    /// Expected outputs in the units of the output layer
+    pub fn normalize_targets(&self, targets: &[f32]) -> Vec<f32> {
+        apply(self.normalization.as_ref().map(|n| &n.outputs), targets, false)
+    }
+
+    pub fn denormalize_outputs(&self, outputs: &[f32]) -> Vec<f32> {
+        apply(self.normalization.as_ref().map(|n| &n.outputs), outputs, true)
+    }
+
+    /// Outputs of the last forward pass in the original units
+    pub fn predictions(&self) -> Vec<f32> {
+        let outputs: Vec<f32> = self.last().neurons.iter().map(|n| n.output).collect();
+        self.denormalize_outputs(&outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::Sample;
+    use crate::normalization::{Normalization, Scaler, Scaling};
+
+    #[test]
+    fn scalers_invert() {
+        let values = [-110.0, -3.0, 0.0, 2.5, 90.0];
+        for scaling in [Scaling::None, Scaling::MinMax, Scaling::ZScore, Scaling::Log] {
+            let scaler = Scaler::fit(scaling, &values);
+            for value in values {
+                let restored = scaler.invert(scaler.apply(value));
+                assert!((restored - value).abs() < 1e-3 * value.abs().max(1.0), "{scaler:?}: {value} -> {restored}");
+            }
+        }
+        let min_max = Scaler::fit(Scaling::MinMax, &values);
+        assert_eq!((min_max.apply(-110.0), min_max.apply(90.0)), (0.0, 1.0));
+        let constant = Scaler::fit(Scaling::ZScore, &[4.0, 4.0]);
+        assert_eq!(constant.apply(5.0), 1.0);
+    }
+
+    #[test]
+    fn fitted_per_column() {
+        let samples = vec![
+            Sample { inputs: vec![0.0, 10.0], outputs: vec![-5.0] },
+            Sample { inputs: vec![2.0, 30.0], outputs: vec![5.0] },
+        ];
+        let normalization = Normalization::fit(&samples, Scaling::MinMax, Scaling::ZScore);
+        assert_eq!(normalization.inputs, vec![Scaler::MinMax { min: 0.0, max: 2.0 }, Scaler::MinMax { min: 10.0, max: 30.0 }]);
+        assert_eq!(normalization.outputs, vec![Scaler::ZScore { mean: 0.0, std_dev: 5.0 }]);
+    }
+
+    #[test]
+    fn column_without_values() {
+        let masked = vec![Sample { inputs: vec![1.0], outputs: vec![f32::NAN] }];
+        for scaling in [Scaling::MinMax, Scaling::ZScore] {
+            assert_eq!(Normalization::fit(&masked, scaling, scaling).outputs, vec![Scaler::Identity]);
+            assert_eq!(Scaler::fit(scaling, &[]), Scaler::Identity);
+        }
+        assert_eq!(Normalization::fit(&[], Scaling::MinMax, Scaling::MinMax), Normalization { inputs: vec![], outputs: vec![] });
+    }
+}
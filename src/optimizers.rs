@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Optimizer and its hyperparameters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum OptimizerFunction {
+    #[default]
+    Sgd,
+    Momentum { momentum: f32 },
+    Nesterov { momentum: f32 },
+    RmsProp { decay: f32, epsilon: f32 },
+    Adagrad { epsilon: f32 },
+    Adam { beta1: f32, beta2: f32, epsilon: f32 },
+}
+
+impl FromStr for OptimizerFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sgd" => Ok(OptimizerFunction::Sgd),
+            "momentum" => Ok(OptimizerFunction::Momentum { momentum: 0.9 }),
+            "nesterov" => Ok(OptimizerFunction::Nesterov { momentum: 0.9 }),
+            "rmsprop" => Ok(OptimizerFunction::RmsProp { decay: 0.9, epsilon: 1e-8 }),
+            "adagrad" => Ok(OptimizerFunction::Adagrad { epsilon: 1e-8 }),
+            "adam" => Ok(OptimizerFunction::Adam { beta1: 0.9, beta2: 0.999, epsilon: 1e-8 }),
+            _ => Err(format!("unknown optimizer: {s}")),
+        }
+    }
+}
+
+/// Everything needed to continue training with the same optimizer.
+/// Moments are indexed by parameter, see `backpropagation::accumulate_gradients` for the order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OptimizerState {
+    pub function: OptimizerFunction,
+    pub step: u64,
+    pub first_moment: Vec<f32>,
+    pub second_moment: Vec<f32>,
+}
+
+impl OptimizerState {
+    pub fn new(function: OptimizerFunction) -> Self {
+        OptimizerState { function, ..Default::default() }
+    }
+}
+
+/// Turns the gradient of a parameter into its change.
+/// `gradient` is `-dL/d(parameter)` (see `backpropagation`), the result is added to the parameter.
+pub trait Optimizer {
+    /// Called once before the parameters of a step are updated
+    fn begin_step(&mut self) {}
+    fn delta(&mut self, index: usize, gradient: f32, learning_rate: f32) -> f32;
+    fn state(&self) -> OptimizerState;
+}
+
+pub fn build(state: OptimizerState) -> Box<dyn Optimizer> {
+    match state.function {
+        OptimizerFunction::Sgd => Box::new(Sgd { state }),
+        OptimizerFunction::Momentum { momentum } => Box::new(Momentum { momentum, nesterov: false, state }),
+        OptimizerFunction::Nesterov { momentum } => Box::new(Momentum { momentum, nesterov: true, state }),
+        OptimizerFunction::RmsProp { decay, epsilon } => Box::new(RmsProp { decay, epsilon, state }),
+        OptimizerFunction::Adagrad { epsilon } => Box::new(Adagrad { epsilon, state }),
+        OptimizerFunction::Adam { beta1, beta2, epsilon } => Box::new(Adam { beta1, beta2, epsilon, state }),
+    }
+}
+
+fn moment(moments: &mut Vec<f32>, index: usize) -> &mut f32 {
+    if moments.len() <= index {
+        moments.resize(index + 1, 0.0);
+    }
+    &mut moments[index]
+}
+
+pub struct Sgd {
+    state: OptimizerState,
+}
+
+impl Optimizer for Sgd {
+    fn begin_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn delta(&mut self, _index: usize, gradient: f32, learning_rate: f32) -> f32 {
+        gradient * learning_rate
+    }
+    fn state(&self) -> OptimizerState {
+        self.state.clone()
+    }
+}
+
+/// Heavy ball momentum, optionally with Nesterov look-ahead
+pub struct Momentum {
+    momentum: f32,
+    nesterov: bool,
+    state: OptimizerState,
+}
+
+impl Optimizer for Momentum {
+    fn begin_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn delta(&mut self, index: usize, gradient: f32, learning_rate: f32) -> f32 {
+        let velocity = moment(&mut self.state.first_moment, index);
+        *velocity = self.momentum * *velocity + gradient;
+        if self.nesterov {
+            (gradient + self.momentum * *velocity) * learning_rate
+        } else {
+            *velocity * learning_rate
+        }
+    }
+    fn state(&self) -> OptimizerState {
+        self.state.clone()
+    }
+}
+
+pub struct RmsProp {
+    decay: f32,
+    epsilon: f32,
+    state: OptimizerState,
+}
+
+impl Optimizer for RmsProp {
+    fn begin_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn delta(&mut self, index: usize, gradient: f32, learning_rate: f32) -> f32 {
+        let square = moment(&mut self.state.second_moment, index);
+        *square = self.decay * *square + (1.0 - self.decay) * gradient * gradient;
+        learning_rate * gradient / (square.sqrt() + self.epsilon)
+    }
+    fn state(&self) -> OptimizerState {
+        self.state.clone()
+    }
+}
+
+pub struct Adagrad {
+    epsilon: f32,
+    state: OptimizerState,
+}
+
+impl Optimizer for Adagrad {
+    fn begin_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn delta(&mut self, index: usize, gradient: f32, learning_rate: f32) -> f32 {
+        let square_sum = moment(&mut self.state.second_moment, index);
+        *square_sum += gradient * gradient;
+        learning_rate * gradient / (square_sum.sqrt() + self.epsilon)
+    }
+    fn state(&self) -> OptimizerState {
+        self.state.clone()
+    }
+}
+
+pub struct Adam {
+    beta1: f32,
+    beta2: f32,
+    epsilon: f32,
+    state: OptimizerState,
+}
+
+impl Optimizer for Adam {
+    fn begin_step(&mut self) {
+        self.state.step += 1;
+    }
+    fn delta(&mut self, index: usize, gradient: f32, learning_rate: f32) -> f32 {
+        let step = self.state.step.max(1) as i32;
+        let m = moment(&mut self.state.first_moment, index);
+        *m = self.beta1 * *m + (1.0 - self.beta1) * gradient;
+        let m_hat = *m / (1.0 - self.beta1.powi(step));
+        let v = moment(&mut self.state.second_moment, index);
+        *v = self.beta2 * *v + (1.0 - self.beta2) * gradient * gradient;
+        let v_hat = *v / (1.0 - self.beta2.powi(step));
+        learning_rate * m_hat / (v_hat.sqrt() + self.epsilon)
+    }
+    fn state(&self) -> OptimizerState {
+        self.state.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::optimizers::{build, Optimizer, OptimizerFunction, OptimizerState};
+
+    const ALL: [&str; 6] = ["sgd", "momentum", "nesterov", "rmsprop", "adagrad", "adam"];
+
+    /// Minimizes (w - 3)^2 starting from 0
+    fn run(optimizer: &mut dyn Optimizer, w: &mut f32, steps: usize) {
+        for _ in 0..steps {
+            optimizer.begin_step();
+            let gradient = -2.0 * (*w - 3.0);
+            *w += optimizer.delta(0, gradient, 0.05);
+        }
+    }
+
+    #[test]
+    fn converges_on_parabola() {
+        for name in ALL {
+            let function: OptimizerFunction = name.parse().unwrap();
+            let mut optimizer = build(OptimizerState::new(function));
+            let mut w = 0.0;
+            run(optimizer.as_mut(), &mut w, 10000);
+            assert!((w - 3.0).abs() < 0.05, "{name}: w = {w}");
+        }
+    }
+
+    #[test]
+    fn resume_from_saved_state() {
+        for name in ALL {
+            let function: OptimizerFunction = name.parse().unwrap();
+            let mut continuous = build(OptimizerState::new(function.clone()));
+            let mut w_continuous = 0.0;
+            run(continuous.as_mut(), &mut w_continuous, 20);
+
+            let mut first = build(OptimizerState::new(function));
+            let mut w_resumed = 0.0;
+            run(first.as_mut(), &mut w_resumed, 10);
+            let json = serde_json::to_string(&first.state()).unwrap();
+            let mut resumed = build(serde_json::from_str(&json).unwrap());
+            run(resumed.as_mut(), &mut w_resumed, 10);
+
+            assert_eq!(w_continuous, w_resumed, "{name}");
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Training progress, printed to stdout and optionally appended to a log file
+pub struct ProgressLog {
+    file: Option<File>,
+}
+
+impl ProgressLog {
+    pub fn new(path: Option<&Path>) -> std::io::Result<Self> {
+        let file = match path {
+            Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+            None => None,
+        };
+        Ok(ProgressLog { file })
+    }
+
+    pub fn line(&mut self, text: &str) {
+        println!("{text}");
+        if let Some(file) = self.file.as_mut() && let Err(e) = writeln!(file, "{text}") {
+            eprintln!("can't write the log: {e}");
+            self.file = None;
+        }
+    }
+}
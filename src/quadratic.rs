@@ -0,0 +1,159 @@
+//! ax^2 + bx + c = 0: the solver behind the quadratic dataset and its generator
+use crate::dataset::Sample;
+use crate::generators::{Generator, Range};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RootsKind {
+    /// Discriminant > 0
+    TwoReal,
+    /// Discriminant = 0, x1 = x2
+    One,
+    /// Discriminant < 0, conjugate roots
+    Complex,
+    /// a = 0, not a quadratic equation
+    Degenerate,
+}
+
+/// Roots as (real, imaginary) parts, x1 has the minus before the root of the discriminant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Roots {
+    pub kind: RootsKind,
+    pub x1: (f32, f32),
+    pub x2: (f32, f32),
+}
+
+pub fn solve(a: f32, b: f32, c: f32) -> Roots {
+    let d = b * b - 4.0 * a * c;
+    if a == 0.0 {
+        return Roots { kind: RootsKind::Degenerate, x1: (f32::NAN, 0.0), x2: (f32::NAN, 0.0) };
+    }
+    if d < 0.0 {
+        let (re, im) = (-b / (2.0 * a), (-d).sqrt() / (2.0 * a));
+        return Roots { kind: RootsKind::Complex, x1: (re, -im), x2: (re, im) };
+    }
+    let kind = if d == 0.0 { RootsKind::One } else { RootsKind::TwoReal };
+    Roots { kind, x1: ((-b - d.sqrt()) / (2.0 * a), 0.0), x2: ((-b + d.sqrt()) / (2.0 * a), 0.0) }
+}
+
+/// What the network is taught to answer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum QuadraticOutput {
+    /// x1, x2, real roots only
+    Roots,
+    /// x1_re, x1_im, x2_re, x2_im
+    Complex,
+    /// Number of distinct real roots: 0, 1 or 2
+    Class,
+}
+
+/// a, b, c drawn from the range; equations of the `skip` kinds are drawn again,
+/// the targets of the `mask` kinds are left out (NaN, see [`Sample`])
+pub struct QuadraticGenerator {
+    pub output: QuadraticOutput,
+    pub skip: Vec<RootsKind>,
+    pub mask: Vec<RootsKind>,
+}
+
+impl Generator for QuadraticGenerator {
+    fn describe(&self) -> String {
+        let output = match self.output {
+            QuadraticOutput::Roots => "roots",
+            QuadraticOutput::Complex => "complex roots",
+            QuadraticOutput::Class => "number of real roots",
+        };
+        format!("{output} of ax^2 + bx + c = 0")
+    }
+    fn inputs(&self) -> Vec<&'static str> {
+        vec!["a", "b", "c"]
+    }
+    fn outputs(&self) -> Vec<&'static str> {
+        match self.output {
+            QuadraticOutput::Roots => vec!["x1", "x2"],
+            QuadraticOutput::Complex => vec!["x1_re", "x1_im", "x2_re", "x2_im"],
+            QuadraticOutput::Class => vec!["roots"],
+        }
+    }
+    fn sample(&self, range: &Range, rng: &mut dyn RngCore) -> Option<Sample> {
+        let (a, b, c) = (range.draw(rng), range.draw(rng), range.draw(rng));
+        let roots = solve(a, b, c);
+        if self.skip.contains(&roots.kind) {
+            return None;
+        }
+        let outputs = if self.mask.contains(&roots.kind) {
+            vec![f32::NAN; self.outputs().len()]
+        } else {
+            match (self.output, roots.kind) {
+                (QuadraticOutput::Class, RootsKind::TwoReal) => vec![2.0],
+                (QuadraticOutput::Class, RootsKind::One) => vec![1.0],
+                (QuadraticOutput::Class, _) => vec![0.0],
+                //у комплексных корней нет вещественных значений, а у вырожденного уравнения - корней
+                (QuadraticOutput::Roots, RootsKind::Complex | RootsKind::Degenerate) => vec![f32::NAN; 2],
+                (QuadraticOutput::Roots, _) => vec![roots.x1.0, roots.x2.0],
+                (QuadraticOutput::Complex, RootsKind::Degenerate) => vec![f32::NAN; 4],
+                (QuadraticOutput::Complex, _) => vec![roots.x1.0, roots.x1.1, roots.x2.0, roots.x2.1],
+            }
+        };
+        Some(Sample { inputs: vec![a, b, c], outputs })
+    }
+    fn classification(&self) -> bool {
+        self.output == QuadraticOutput::Class
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generators::{generate, GeneratorConfig, GeneratorFunction};
+    use crate::quadratic::{solve, QuadraticOutput, RootsKind};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn every_kind() {
+        let two = solve(2.0, -6.0, 4.0);
+        assert_eq!((two.kind, two.x1, two.x2), (RootsKind::TwoReal, (1.0, 0.0), (2.0, 0.0)));
+        let one = solve(1.0, -4.0, 4.0);
+        assert_eq!((one.kind, one.x1, one.x2), (RootsKind::One, (2.0, 0.0), (2.0, 0.0)));
+        let complex = solve(1.0, 2.0, 5.0);
+        assert_eq!((complex.kind, complex.x1, complex.x2), (RootsKind::Complex, (-1.0, -2.0), (-1.0, 2.0)));
+        assert_eq!(solve(0.0, 2.0, 1.0).kind, RootsKind::Degenerate);
+    }
+
+    #[test]
+    fn skipped_and_masked_kinds() {
+        let config = |output, skip, mask| GeneratorConfig {
+            function: GeneratorFunction::QuadraticRoots { output, skip, mask },
+            count: 300,
+            ..GeneratorConfig::default()
+        };
+        let rng = || ChaCha8Rng::seed_from_u64(8);
+
+        let masked = generate(&config(QuadraticOutput::Roots, vec![RootsKind::Degenerate], vec![RootsKind::Complex]), &mut rng()).unwrap();
+        for sample in masked.samples.iter() {
+            let [a, b, c] = sample.inputs[..] else { panic!() };
+            assert_eq!(sample.outputs.iter().all(|x| x.is_nan()), solve(a, b, c).kind == RootsKind::Complex, "{sample:?}");
+        }
+
+        let classes = generate(&config(QuadraticOutput::Class, vec![RootsKind::Degenerate], vec![]), &mut rng()).unwrap();
+        for label in [0.0, 1.0, 2.0] {
+            assert!(classes.samples.iter().any(|s| s.outputs == [label]), "no samples of class {label}");
+        }
+
+        let noisy = GeneratorConfig { noise: 0.5, ..config(QuadraticOutput::Class, vec![RootsKind::Degenerate], vec![]) };
+        let noisy = generate(&noisy, &mut rng()).unwrap();
+        //шум идёт в a, b, c, а метки остаются классами
+        assert!(noisy.samples.iter().all(|s| [0.0, 1.0, 2.0].contains(&s.outputs[0])));
+        assert!(noisy.samples.iter().flat_map(|s| &s.inputs).any(|v| v.fract() != 0.0));
+
+        let complex = generate(&config(QuadraticOutput::Complex, vec![RootsKind::TwoReal, RootsKind::One, RootsKind::Degenerate], vec![]), &mut rng()).unwrap();
+        assert_eq!(complex.outputs, ["x1_re", "x1_im", "x2_re", "x2_im"]);
+        assert!(complex.samples.iter().all(|s| s.outputs[1] == -s.outputs[3] && s.outputs[3] != 0.0));
+
+        let degenerate = GeneratorConfig { min: 0.0, max: 1.0, ..config(QuadraticOutput::Complex, vec![], vec![]) };
+        let degenerate = generate(&degenerate, &mut rng()).unwrap();
+        let without_roots: Vec<_> = degenerate.samples.iter().filter(|s| s.inputs[0] == 0.0).collect();
+        assert!(!without_roots.is_empty());
+        assert!(without_roots.iter().all(|s| s.outputs.iter().all(|x| x.is_nan())), "{without_roots:?}");
+    }
+}
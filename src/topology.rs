@@ -0,0 +1,165 @@
+use crate::initializers::Initializer;
+use crate::nn_objects::{ActivationFunction, Layer, Link, Network, Neuron};
+use crate::validation::describe;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Compact description of a network, compiled into a [`Network`] by [`TopologySpec::build`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologySpec {
+    /// Ids of the input neurons
+    pub inputs: Vec<String>,
+    pub layers: Vec<LayerSpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerSpec {
+    pub neurons: NeuronIds,
+    pub activation: ActivationFunction,
+    #[serde(default)]
+    pub connectivity: Connectivity,
+    pub initializer: Initializer,
+    /// Probability to drop a neuron while training
+    #[serde(default)]
+    pub dropout: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NeuronIds {
+    /// ["m1", "m2"]
+    Names(Vec<String>),
+    /// {"size": 16, "prefix": "h"} -> h1..h16
+    Generated { size: usize, prefix: String },
+}
+
+impl NeuronIds {
+    fn ids(&self) -> Vec<String> {
+        match self {
+            NeuronIds::Names(names) => names.clone(),
+            NeuronIds::Generated { size, prefix } => (1..=*size).map(|i| format!("{prefix}{i}")).collect(),
+        }
+    }
+}
+
+/// Which neurons of the previous layer feed a neuron
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum Connectivity {
+    /// Every neuron of the previous layer
+    #[default]
+    Full,
+    /// Neuron i of the previous layer only, layers have to be of the same size
+    OneToOne,
+}
+
+impl TopologySpec {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn build(&self, rng: &mut impl Rng) -> Result<Network, String> {
+        let mut known_ids = HashSet::new();
+        let mut check_id = |id: &String| {
+            if !known_ids.insert(id.clone()) {
+                return Err(format!("duplicate neuron id: {id}"));
+            }
+            Ok(())
+        };
+
+        if self.inputs.is_empty() {
+            return Err("no inputs".to_string());
+        }
+        for id in self.inputs.iter() {
+            check_id(id)?;
+        }
+        let mut layers = vec![Layer::new(self.inputs.iter().map(|id| Neuron::new_input(id.clone())).collect())];
+        let mut prev_ids = self.inputs.clone();
+
+        for (index, layer_spec) in self.layers.iter().enumerate() {
+            let ids = layer_spec.neurons.ids();
+            if ids.is_empty() {
+                return Err(format!("layer {} has no neurons", index + 1));
+            }
+            if !(0.0..1.0).contains(&layer_spec.dropout) {
+                return Err(format!("layer {} dropout has to be in [0, 1): {}", index + 1, layer_spec.dropout));
+            }
+            layer_spec.initializer.validate().map_err(|e| format!("layer {} initializer: {e}", index + 1))?;
+            if matches!(layer_spec.connectivity, Connectivity::OneToOne) && ids.len() != prev_ids.len() {
+                return Err(format!("layer {} is one-to-one with {} neurons, previous layer has {}",
+                                   index + 1, ids.len(), prev_ids.len()));
+            }
+            let mut neurons = vec![];
+            for (neuron_index, id) in ids.iter().enumerate() {
+                check_id(id)?;
+                let sources: Vec<&String> = match layer_spec.connectivity {
+                    Connectivity::Full => prev_ids.iter().collect(),
+                    Connectivity::OneToOne => vec![&prev_ids[neuron_index]],
+                };
+                let fan_in = sources.len();
+                let fan_out = ids.len();
+                let links = sources.into_iter()
+                    .map(|source| Link::new(source.clone(), layer_spec.initializer.weight(fan_in, fan_out, rng)))
+                    .collect();
+                neurons.push(Neuron::new_middle(id.clone(), 0.0, layer_spec.activation.clone(), links));
+            }
+            let mut layer = Layer::new(neurons);
+            layer.dropout = layer_spec.dropout;
+            layers.push(layer);
+            prev_ids = ids;
+        }
+        let nn = Network::new(layers);
+        nn.validate().map_err(|errors| describe(&errors))?;
+        Ok(nn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::topology::TopologySpec;
+
+    #[test]
+    fn build_generated_layers() {
+        let spec = TopologySpec::from_json(r#"{
+            "inputs": ["a", "b"],
+            "layers": [
+                {"neurons": {"size": 16, "prefix": "h"}, "activation": "Relu", "initializer": "HeNormal"},
+                {"neurons": {"size": 16, "prefix": "g"}, "activation": "Relu", "connectivity": "OneToOne",
+                 "initializer": {"Uniform": {"min": -1.0, "max": 1.0}}},
+                {"neurons": ["y"], "activation": "Linear", "initializer": {"Constant": 0.5}}
+            ]
+        }"#).unwrap();
+        let nn = spec.build(&mut rand::rng()).unwrap();
+        assert_eq!(nn.layers_count, 4);
+        assert_eq!(nn.layers[1].neurons.len(), 16);
+        assert_eq!(nn.layers[1].neurons[15].id, "h16");
+        assert_eq!(nn.layers[1].neurons[0].input_links.len(), 2);
+        assert_eq!(nn.layers[2].neurons[3].input_links.len(), 1);
+        assert_eq!(nn.layers[2].neurons[3].input_links[0].source_id, "h4");
+        assert_eq!(nn.last().neurons[0].input_links.len(), 16);
+        assert!(nn.layers[1].neurons.iter().flat_map(|n| n.input_links.iter()).any(|l| l.weight != 0.0));
+        assert!(nn.last().neurons[0].input_links.iter().all(|l| l.weight == 0.5));
+    }
+
+    #[test]
+    fn duplicate_ids_rejected() {
+        let spec = TopologySpec::from_json(r#"{
+            "inputs": ["a"],
+            "layers": [{"neurons": ["a"], "activation": "Linear", "initializer": {"Constant": 0.1}}]
+        }"#).unwrap();
+        assert_eq!(spec.build(&mut rand::rng()).unwrap_err(), "duplicate neuron id: a");
+    }
+
+    #[test]
+    fn invalid_initializer_rejected() {
+        let build = |initializer: &str| TopologySpec::from_json(&format!(r#"{{
+            "inputs": ["a"],
+            "layers": [{{"neurons": ["y"], "activation": "Linear", "initializer": {initializer}}}]
+        }}"#)).unwrap().build(&mut rand::rng());
+        assert_eq!(build(r#"{"Uniform": {"min": 1.0, "max": 1.0}}"#).unwrap_err(),
+                   "layer 1 initializer: uniform range 1..1 has to be finite with min < max");
+        assert!(build(r#"{"Uniform": {"min": 2.0, "max": -1.0}}"#).is_err());
+        assert!(build(r#"{"Normal": {"mean": 0.0, "std_dev": -0.5}}"#).is_err());
+        assert!(build(r#"{"Normal": {"mean": 0.0, "std_dev": 0.0}}"#).is_ok());
+    }
+}
@@ -1,107 +1,124 @@
 use rand::seq::SliceRandom;
 use rand::Rng;
-use serde::{Deserialize, Serialize};
-use std::fs;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TrainItem {
-    pub a: f32,
-    pub b: f32,
-    pub c: f32,
-    pub x1: f32,
-    pub x2: f32,
+pub fn shuffle<T>(train_items: &mut [T], rng: &mut impl Rng) {
+    train_items.shuffle(rng);
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TrainItemCommon {
-    pub input_1: f32,
-    pub input_2: f32,
-    pub input_3: f32,
-    pub input_4: f32,
-    pub output_1: f32,
-    pub output_2: f32,
-    pub output_3: f32,
-    pub output_4: f32,
+pub struct DataSplit<T> {
+    pub train: Vec<T>,
+    pub validation: Vec<T>,
+    pub test: Vec<T>,
 }
 
-pub fn load_kx_b() -> Vec<TrainItemCommon>{
-    let mut rng = rand::rng();
-    let mut result = vec![];
-    
-    for _i in 0..100 {
-        let k: f32 = rng.random_range(-10..10) as f32;
-        let x: f32 = rng.random_range(-10..10) as f32;
-        let b: f32 = rng.random_range(-10..10) as f32;
-        let y = k * x + b;       
-        result.push(TrainItemCommon{
-            input_1: k,
-            input_2: x,
-            input_3: b,
-            input_4: 0.0,
-            output_1: y,
-            output_2:0.0,
-            output_3:0.0,
-            output_4:0.0
-        })
+/// Shuffles the items and cuts the validation and test parts off the end.
+/// Fails when the fractions leave nothing to train on
+pub fn split<T>(mut items: Vec<T>, validation_fraction: f32, test_fraction: f32, rng: &mut impl Rng) -> Result<DataSplit<T>, String> {
+    for (name, fraction) in [("validation", validation_fraction), ("test", test_fraction)] {
+        if !(0.0..1.0).contains(&fraction) {
+            return Err(format!("{name} fraction has to be in [0, 1), got {fraction}"));
+        }
     }
-    result    
-}
-
-
-pub fn load_train() -> Result<Vec<TrainItem>, Box<dyn std::error::Error>> {
-    let train_file_content = fs::read_to_string("./train.json")?;
-    let train_items: Vec<TrainItem> = serde_json::from_str(&train_file_content)?;
-    Ok(train_items)
-}
-
-pub fn shuffle<T>(train_items: &mut [T]) {
-    let mut rng = rand::rng();
-    train_items.shuffle(&mut rng);
+    if validation_fraction + test_fraction >= 1.0 {
+        return Err(format!("validation and test fractions sum to {}, nothing is left to train on", validation_fraction + test_fraction));
+    }
+    shuffle(&mut items, rng);
+    let test_count = (items.len() as f32 * test_fraction).round() as usize;
+    let validation_count = (items.len() as f32 * validation_fraction).round() as usize;
+    let test = items.split_off(items.len() - test_count.min(items.len()));
+    let validation = items.split_off(items.len() - validation_count.min(items.len()));
+    if items.is_empty() {
+        return Err(format!("no train samples left out of {}", validation.len() + test.len()));
+    }
+    Ok(DataSplit { train: items, validation, test })
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::train_data::{load_train, TrainItem};
+    use crate::generators::{generate, GeneratorConfig};
+    use crate::quadratic::{solve, RootsKind};
+    use crate::train_data::split;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashSet;
     use std::fs;
+    use std::path::Path;
     const EPSILON: f32 = 1e-3;
-    
-    fn calculate_eq(item: &TrainItem) -> (f32, f32) {
-        let d = item.b.powi(2) - 4.0 * item.a * item.c;
-        let x1 = (-item.b - d.sqrt()) / 2.0 * item.a;
-        let x2 = (-item.b + d.sqrt()) / 2.0 * item.a;
-        (x1, x2)
+    const TRAIN_FILE: &str = "neural-networks/ax2_bx_c/train.json";
+
+    /// Sample of `neural-networks/ax2_bx_c/train.json`, roots are null when they are not real
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct TrainItem {
+        a: f32,
+        b: f32,
+        c: f32,
+        x1: Option<f32>,
+        x2: Option<f32>,
+    }
+
+    fn load_train(path: &Path) -> Result<Vec<TrainItem>, Box<dyn std::error::Error>> {
+        let train_file_content = fs::read_to_string(path)?;
+        let train_items: Vec<TrainItem> = serde_json::from_str(&train_file_content)?;
+        Ok(train_items)
     }
-    
+
+    /// Real roots, `None` for complex ones
+    fn calculate_eq(item: &TrainItem) -> (Option<f32>, Option<f32>) {
+        let roots = solve(item.a, item.b, item.c);
+        match roots.kind {
+            RootsKind::TwoReal | RootsKind::One => (Some(roots.x1.0), Some(roots.x2.0)),
+            RootsKind::Complex | RootsKind::Degenerate => (None, None),
+        }
+    }
+
+    /// Recomputes the roots of the train file, cargo test recalculate_train_set -- --ignored
     #[test]
     #[ignore]
     fn recalculate_train_set() {
-        let mut train_data: Vec<TrainItem> = load_train().unwrap();
+        let mut train_data: Vec<TrainItem> = load_train(Path::new(TRAIN_FILE)).unwrap();
         for item in train_data.iter_mut() {
-            let (x1, x2) = calculate_eq(&item);
-            item.x1 = x1;
-            item.x2 = x2;
+            (item.x1, item.x2) = calculate_eq(item);
         }
         let json = serde_json::to_string_pretty(&train_data).unwrap();
         fs::write("train-recalculated.json", json).unwrap();
     }
 
+    #[test]
+    fn split_kx_b() {
+        let mut rng = rand::rng();
+        let data = split(generate(&GeneratorConfig::default(), &mut rng).unwrap().samples, 0.15, 0.2, &mut rng).unwrap();
+        assert_eq!(data.train.len(), 65);
+        assert_eq!(data.validation.len(), 15);
+        assert_eq!(data.test.len(), 20);
+
+        for (validation, test) in [(-0.1, 0.2), (0.2, 1.0), (0.5, 0.5), (f32::NAN, 0.0)] {
+            assert!(split(vec![1, 2, 3], validation, test, &mut rng).is_err(), "{validation} {test}");
+        }
+        assert!(split(vec![1, 2], 0.25, 0.25, &mut rng).is_err());
+    }
+
     #[test]
     fn validate_train_data() {
-        for item in load_train().unwrap() {
+        let mut kinds = HashSet::new();
+        for item in load_train(Path::new(TRAIN_FILE)).unwrap() {
+            let kind = solve(item.a, item.b, item.c).kind;
+            kinds.insert(kind);
             let (x1, x2) = calculate_eq(&item);
-            println!("x1 = {x1}, x2 = {x2}, {:?}", item);            
-            assert!(
-                (x1 - item.x1).abs() < EPSILON,
-                "x1 mismatch: expected {}, got {}",
-                x1,
-                item.x1
-            );
-            assert!(
-                (x2 - item.x2).abs() < EPSILON,
-                "x2 mismatch: expected {}, got {} ",
-                x2,
-                item.x2
-            );
+            for (expected, actual) in [(x1, item.x1), (x2, item.x2)] {
+                match (expected, actual) {
+                    (Some(expected), Some(actual)) => assert!(
+                        (expected - actual).abs() < EPSILON * expected.abs().max(1.0),
+                        "{kind:?}: expected {expected}, got {actual}, {item:?}"
+                    ),
+                    (None, None) => {}
+                    _ => panic!("{kind:?}: expected {expected:?}, got {actual:?}, {item:?}"),
+                }
+            }
+            if kind == RootsKind::One {
+                assert_eq!(item.x1, item.x2, "{item:?}");
+            }
+        }
+        for kind in [RootsKind::TwoReal, RootsKind::One, RootsKind::Complex] {
+            assert!(kinds.contains(&kind), "no {kind:?} equations in {TRAIN_FILE}");
         }
     }
 }
@@ -0,0 +1,194 @@
+use crate::nn_objects::Network;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Structural problem of a [`Network`], see [`Network::validate`].
+/// Layers are numbered from 0, the input layer
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    LayersCountMismatch { layers_count: usize, layers: usize },
+    /// Input and output layers are required
+    TooFewLayers { layers: usize },
+    EmptyLayer { layer: usize },
+    EmptyOutputLayer,
+    DuplicateId { id: String },
+    InputWithLinks { neuron: String },
+    UnknownSource { neuron: String, source: String },
+    /// Source is in an earlier layer than the previous one
+    SkippedLayers { neuron: String, source: String, source_layer: usize, layer: usize },
+    /// Source is in the same or a later layer
+    BackwardLink { neuron: String, source: String, source_layer: usize, layer: usize },
+    /// Neurons feeding each other, in the order of the links
+    Cycle { neurons: Vec<String> },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::LayersCountMismatch { layers_count, layers } =>
+                write!(f, "layers_count is {layers_count}, but there are {layers} layers"),
+            NetworkError::TooFewLayers { layers } => write!(f, "a network needs at least 2 layers, got {layers}"),
+            NetworkError::EmptyLayer { layer } => write!(f, "layer {layer} has no neurons"),
+            NetworkError::EmptyOutputLayer => write!(f, "output layer has no neurons"),
+            NetworkError::DuplicateId { id } => write!(f, "duplicate neuron id: {id}"),
+            NetworkError::InputWithLinks { neuron } => write!(f, "input neuron {neuron} has links"),
+            NetworkError::UnknownSource { neuron, source } =>
+                write!(f, "neuron {neuron} has a link from {source}, which is not in the network"),
+            NetworkError::SkippedLayers { neuron, source, source_layer, layer } =>
+                write!(f, "neuron {neuron} (layer {layer}) has a link from {source} (layer {source_layer}), only the previous layer is allowed"),
+            NetworkError::BackwardLink { neuron, source, source_layer, layer } =>
+                write!(f, "neuron {neuron} (layer {layer}) has a link from {source} (layer {source_layer}), which is not before it"),
+            NetworkError::Cycle { neurons } => write!(f, "cycle: {}", neurons.join(" -> ")),
+        }
+    }
+}
+
+/// All errors in one line
+pub fn describe(errors: &[NetworkError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+impl Network {
+    /// Checks everything the forward and backward passes rely on, reports all problems found
+    pub fn validate(&self) -> Result<(), Vec<NetworkError>> {
+        let mut errors = vec![];
+        if self.layers_count != self.layers.len() {
+            errors.push(NetworkError::LayersCountMismatch { layers_count: self.layers_count, layers: self.layers.len() });
+        }
+        if self.layers.len() < 2 {
+            errors.push(NetworkError::TooFewLayers { layers: self.layers.len() });
+        }
+
+        //слой каждого нейрона, по id
+        let mut layer_of: HashMap<&str, usize> = HashMap::new();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if layer.neurons.is_empty() {
+                errors.push(if index > 0 && index == self.layers.len() - 1 {
+                    NetworkError::EmptyOutputLayer
+                } else {
+                    NetworkError::EmptyLayer { layer: index }
+                });
+            }
+            for neuron in layer.neurons.iter() {
+                if layer_of.contains_key(neuron.id.as_str()) {
+                    errors.push(NetworkError::DuplicateId { id: neuron.id.clone() });
+                } else {
+                    layer_of.insert(&neuron.id, index);
+                }
+            }
+        }
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            for neuron in layer.neurons.iter() {
+                if index == 0 && !neuron.input_links.is_empty() {
+                    errors.push(NetworkError::InputWithLinks { neuron: neuron.id.clone() });
+                    continue;
+                }
+                for link in neuron.input_links.iter() {
+                    let (neuron_id, source) = (neuron.id.clone(), link.source_id.clone());
+                    match layer_of.get(link.source_id.as_str()) {
+                        None => errors.push(NetworkError::UnknownSource { neuron: neuron_id, source }),
+                        Some(&source_layer) if source_layer + 1 < index =>
+                            errors.push(NetworkError::SkippedLayers { neuron: neuron_id, source, source_layer, layer: index }),
+                        Some(&source_layer) if source_layer >= index =>
+                            errors.push(NetworkError::BackwardLink { neuron: neuron_id, source, source_layer, layer: index }),
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+
+        errors.extend(self.cycles().into_iter().map(|neurons| NetworkError::Cycle { neurons }));
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Depth-first search over the links, one cycle per back edge.
+    /// Of neurons with the same id the first one is used
+    fn cycles(&self) -> Vec<Vec<String>> {
+        let mut neurons = HashMap::new();
+        for neuron in self.layers.iter().flat_map(|l| l.neurons.iter()) {
+            neurons.entry(neuron.id.as_str()).or_insert(neuron);
+        }
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark { InProgress, Done }
+        let mut marks: HashMap<&str, Mark> = HashMap::new();
+        let mut cycles = vec![];
+
+        for start in self.layers.iter().flat_map(|l| l.neurons.iter()) {
+            if marks.contains_key(start.id.as_str()) {
+                continue;
+            }
+            //стек: нейрон и индекс следующей связи
+            let mut stack = vec![(start.id.as_str(), 0)];
+            marks.insert(&start.id, Mark::InProgress);
+            while let Some((id, link_index)) = stack.pop() {
+                let links = &neurons[id].input_links;
+                if link_index == links.len() {
+                    marks.insert(id, Mark::Done);
+                    continue;
+                }
+                stack.push((id, link_index + 1));
+                let source = links[link_index].source_id.as_str();
+                if !neurons.contains_key(source) {
+                    continue;
+                }
+                match marks.get(source) {
+                    None => {
+                        marks.insert(source, Mark::InProgress);
+                        stack.push((source, 0));
+                    }
+                    Some(Mark::InProgress) => {
+                        //путь по стеку от source до id, в порядке связей (источник -> приёмник)
+                        let position = stack.iter().position(|(n, _)| *n == source).unwrap();
+                        let mut cycle = vec![source.to_string()];
+                        cycle.extend(stack[position..].iter().rev().map(|(n, _)| n.to_string()));
+                        cycles.push(cycle);
+                    }
+                    Some(Mark::Done) => {}
+                }
+            }
+        }
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nn_build::build_nn;
+    use crate::nn_objects::{ActivationFunction, Layer, Link, Network, Neuron};
+    use crate::validation::NetworkError;
+
+    #[test]
+    fn valid_network() {
+        assert_eq!(build_nn(&mut rand::rng()).validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_all_problems() {
+        let mut nn = build_nn(&mut rand::rng());
+        let link = |source: &str| Link::new(source.to_string(), 1.0);
+        nn.layers[0].neurons[0].input_links.push(link("zz"));
+        nn.layers[1].neurons[0].input_links.push(link("q"));
+        nn.layers[3].neurons[0].input_links.push(link("m2"));
+        nn.layers[1].neurons[1].input_links.push(link("n1"));
+        nn.layers[3].neurons[1].id = "x1".to_string();
+        nn.layers_count = 5;
+        assert_eq!(nn.validate().unwrap_err(), vec![
+            NetworkError::LayersCountMismatch { layers_count: 5, layers: 4 },
+            NetworkError::DuplicateId { id: "x1".to_string() },
+            NetworkError::InputWithLinks { neuron: "a".to_string() },
+            NetworkError::UnknownSource { neuron: "m1".to_string(), source: "q".to_string() },
+            NetworkError::BackwardLink { neuron: "m2".to_string(), source: "n1".to_string(), source_layer: 2, layer: 1 },
+            NetworkError::SkippedLayers { neuron: "x1".to_string(), source: "m2".to_string(), source_layer: 1, layer: 3 },
+            NetworkError::Cycle { neurons: vec!["m2".to_string(), "n1".to_string(), "m2".to_string()] },
+        ]);
+    }
+
+    #[test]
+    fn empty_output_layer() {
+        let input = Neuron::new_input("a".to_string());
+        let hidden = Neuron::new_middle("h".to_string(), 0.0, ActivationFunction::Linear, vec![Link::new("a".to_string(), 1.0)]);
+        let nn = Network::new(vec![Layer::new(vec![input]), Layer::new(vec![hidden]), Layer::new(vec![])]);
+        assert_eq!(nn.validate().unwrap_err(), vec![NetworkError::EmptyOutputLayer]);
+    }
+}